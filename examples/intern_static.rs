@@ -0,0 +1,28 @@
+use std::time::Instant;
+
+use ijson::IString;
+
+const ITERATIONS: u32 = 10_000_000;
+
+fn main() {
+    ijson::string::init_cache();
+
+    // Keep the string alive so that each iteration only measures the lookup.
+    let _keep = IString::intern("type");
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let _ = IString::intern("type");
+    }
+    let intern = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let _ = IString::intern_static("type");
+    }
+    let intern_static = start.elapsed();
+
+    println!("{} iterations", ITERATIONS);
+    println!("IString::intern:        {:?}", intern);
+    println!("IString::intern_static: {:?}", intern_static);
+}
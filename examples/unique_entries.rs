@@ -0,0 +1,30 @@
+use std::time::Instant;
+
+use ijson::{IObject, IString, IValue};
+
+const KEYS: usize = 10_000;
+const ITERATIONS: u32 = 1_000;
+
+fn main() {
+    ijson::string::init_cache();
+
+    // Intern the keys up-front so that only the object construction is measured.
+    let keys: Vec<IString> = (0..KEYS).map(|i| format!("key-{}", i).into()).collect();
+    let entries = || keys.iter().map(|k| (k.clone(), IValue::TRUE));
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let _ = entries().collect::<IObject>();
+    }
+    let from_iter = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let _ = IObject::from_unique_entries(entries());
+    }
+    let from_unique_entries = start.elapsed();
+
+    println!("{} iterations of {} keys", ITERATIONS, KEYS);
+    println!("FromIterator:                 {:?}", from_iter);
+    println!("IObject::from_unique_entries: {:?}", from_unique_entries);
+}
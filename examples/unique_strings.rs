@@ -0,0 +1,47 @@
+use std::time::Instant;
+
+const STRINGS: usize = 100_000;
+const ITERATIONS: u32 = 20;
+
+fn main() {
+    ijson::string::init_cache();
+
+    // A parse-once workload where no string is ever repeated, so interning
+    // cannot save any memory.
+    let doc = serde_json::to_string(
+        &(0..STRINGS)
+            .map(|i| format!("unique string number {}", i))
+            .collect::<Vec<_>>(),
+    )
+    .unwrap();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let _: serde_json::Value = serde_json::from_str(&doc).unwrap();
+    }
+    let serde_json = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let _: ijson::IValue = serde_json::from_str(&doc).unwrap();
+    }
+    let ijson = start.elapsed();
+
+    println!("{} iterations of {} unique strings", ITERATIONS, STRINGS);
+    println!("serde_json::Value: {:?}", serde_json);
+    println!("ijson::IValue:     {:?}", ijson);
+
+    #[cfg(feature = "direct_strings")]
+    {
+        use serde::de::DeserializeSeed;
+
+        let limits = ijson::DeserializeLimits::new().direct_strings(true);
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            let _ = limits
+                .deserialize(&mut serde_json::Deserializer::from_str(&doc))
+                .unwrap();
+        }
+        println!("direct strings:    {:?}", start.elapsed());
+    }
+}
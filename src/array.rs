@@ -3,24 +3,46 @@
 use std::alloc::{alloc, dealloc, realloc, Layout, LayoutError};
 use std::borrow::{Borrow, BorrowMut};
 use std::cmp::{self, Ordering};
+use std::collections::HashSet;
+use std::convert::TryInto;
 use std::fmt::{self, Debug, Formatter};
 use std::hash::Hash;
 use std::iter::FromIterator;
-use std::ops::{Deref, DerefMut, Index, IndexMut};
+use std::ops::{Bound, Deref, DerefMut, Index, IndexMut, RangeBounds};
 use std::slice::SliceIndex;
+#[cfg(feature = "snapshot")]
+use std::sync::atomic::{fence, AtomicUsize, Ordering as AtomicOrdering};
 
 use crate::thin::{ThinMut, ThinMutExt, ThinRef, ThinRefExt};
 
-use super::value::{IValue, TypeTag};
+use super::string::IString;
+use super::value::{IValue, TypeTag, ValueType};
 
 #[repr(C)]
 #[repr(align(4))]
 struct Header {
+    // Number of `IArray`s sharing this allocation, see `IArray::snapshot`.
+    #[cfg(feature = "snapshot")]
+    rc: AtomicUsize,
     len: usize,
-    cap: usize,
+    // The capacity, with the growth factor (see `IArray::with_capacity_and_growth`)
+    // packed into the top bits, which a valid capacity never uses.
+    cap_and_growth: usize,
 }
 
+const GROWTH_BITS: u32 = 3;
+const GROWTH_SHIFT: u32 = usize::BITS - GROWTH_BITS;
+const CAP_MASK: usize = usize::MAX >> GROWTH_BITS;
+
 trait HeaderRef<'a>: ThinRefExt<'a, Header> {
+    fn cap(&self) -> usize {
+        self.cap_and_growth & CAP_MASK
+    }
+    // Zero means the default of doubling, otherwise the array grows by
+    // `1 + growth / 8` times.
+    fn growth(&self) -> usize {
+        self.cap_and_growth >> GROWTH_SHIFT
+    }
     fn array_ptr(&self) -> *const IValue {
         // Safety: pointers to the end of structs are allowed
         unsafe { self.ptr().add(1).cast::<IValue>() }
@@ -90,6 +112,34 @@ impl Debug for IntoIter {
     }
 }
 
+/// Iterator over [`IValue`]s returned from [`IArray::into_iter_rev`]
+pub struct IntoIterRev {
+    array: IArray,
+}
+
+impl Iterator for IntoIterRev {
+    type Item = IValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.array.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.array.len();
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for IntoIterRev {}
+
+impl Debug for IntoIterRev {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IntoIterRev")
+            .field("array", &self.array)
+            .finish()
+    }
+}
+
 /// The `IArray` type is similar to a `Vec<IValue>`. The primary difference is
 /// that the length and capacity are stored _inside_ the heap allocation, so that
 /// the `IArray` itself can be a single pointer.
@@ -99,7 +149,12 @@ pub struct IArray(pub(crate) IValue);
 
 value_subtype_impls!(IArray, into_array, as_array, as_array_mut);
 
-static EMPTY_HEADER: Header = Header { len: 0, cap: 0 };
+static EMPTY_HEADER: Header = Header {
+    #[cfg(feature = "snapshot")]
+    rc: AtomicUsize::new(0),
+    len: 0,
+    cap_and_growth: 0,
+};
 
 impl IArray {
     fn layout(cap: usize) -> Result<Layout, LayoutError> {
@@ -112,24 +167,29 @@ impl IArray {
     fn alloc(cap: usize) -> *mut Header {
         unsafe {
             let ptr = alloc(Self::layout(cap).unwrap()).cast::<Header>();
-            ptr.write(Header { len: 0, cap });
+            ptr.write(Header {
+                #[cfg(feature = "snapshot")]
+                rc: AtomicUsize::new(1),
+                len: 0,
+                cap_and_growth: cap,
+            });
             ptr
         }
     }
 
     fn realloc(ptr: *mut Header, new_cap: usize) -> *mut Header {
         unsafe {
-            let old_layout = Self::layout((*ptr).cap).unwrap();
+            let old_layout = Self::layout(ThinRef::new(ptr).cap()).unwrap();
             let new_layout = Self::layout(new_cap).unwrap();
             let ptr = realloc(ptr.cast::<u8>(), old_layout, new_layout.size()).cast::<Header>();
-            (*ptr).cap = new_cap;
+            (*ptr).cap_and_growth = new_cap | ((*ptr).cap_and_growth & !CAP_MASK);
             ptr
         }
     }
 
     fn dealloc(ptr: *mut Header) {
         unsafe {
-            let layout = Self::layout((*ptr).cap).unwrap();
+            let layout = Self::layout(ThinRef::new(ptr).cap()).unwrap();
             dealloc(ptr.cast(), layout);
         }
     }
@@ -151,23 +211,159 @@ impl IArray {
         }
     }
 
-    fn header(&self) -> ThinRef<Header> {
+    /// Constructs a new `IArray` with the specified capacity, which multiplies
+    /// its capacity by `factor` rather than doubling it whenever it has to grow.
+    ///
+    /// A smaller factor wastes less memory on unused capacity, at the cost of
+    /// reallocating more often. The factor is stored in the array's allocation
+    /// and rounded to the nearest eighth, so the array is allocated even if
+    /// `cap` is zero. Like the capacity, it is not kept by clones, or if the
+    /// array is shrunk to no capacity at all.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is not greater than `1.0` and at most `2.0`.
+    #[must_use]
+    pub fn with_capacity_and_growth(cap: usize, factor: f64) -> Self {
+        assert!(
+            factor > 1.0 && factor <= 2.0,
+            "growth factor must be greater than 1.0 and at most 2.0"
+        );
+        let growth = (((factor - 1.0) * 8.0).round() as usize).clamp(1, 8) % 8;
+        let mut res = Self::with_capacity(cap.max(1));
+        res.set_growth(growth);
+        res
+    }
+
+    // Has no effect on an array with no capacity.
+    fn set_growth(&mut self, growth: usize) {
+        if !self.is_static() {
+            // Safety: not static
+            let mut hd = unsafe { self.header_mut() };
+            hd.cap_and_growth = hd.cap() | (growth << GROWTH_SHIFT);
+        }
+    }
+
+    /// Constructs a new `IArray` from an iterator of fallible items, stopping
+    /// at the first error.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error produced by `iter`, without consuming any
+    /// further items.
+    pub fn try_from_iter<T: Into<IValue>, E>(
+        iter: impl IntoIterator<Item = Result<T, E>>,
+    ) -> Result<Self, E> {
+        let iter = iter.into_iter();
+        let mut res = IArray::with_capacity(iter.size_hint().0);
+        for item in iter {
+            res.push(item?);
+        }
+        Ok(res)
+    }
+
+    /// Constructs a new `IArray` containing `n` clones of `value`, like
+    /// `vec![value; n]`.
+    #[must_use]
+    pub fn repeat(value: &IValue, n: usize) -> Self {
+        let mut res = Self::with_capacity(n);
+        for _ in 0..n {
+            // Safety: We reserved enough space for every item up-front
+            unsafe {
+                res.header_mut().push(value.clone());
+            }
+        }
+        res
+    }
+
+    /// Returns a new `IArray` containing the result of calling `f` on each item
+    /// of this array, in order. Space for every item is allocated up-front.
+    #[must_use]
+    pub fn map(&self, mut f: impl FnMut(&IValue) -> IValue) -> Self {
+        let mut res = Self::with_capacity(self.len());
+        for v in self {
+            let item = f(v);
+            // Safety: We reserved enough space for every item up-front
+            unsafe {
+                res.header_mut().push(item);
+            }
+        }
+        res
+    }
+
+    fn header(&self) -> ThinRef<'_, Header> {
         unsafe { ThinRef::new(self.0.ptr().cast()) }
     }
 
     // Safety: must not be static
-    unsafe fn header_mut(&mut self) -> ThinMut<Header> {
+    unsafe fn header_mut(&mut self) -> ThinMut<'_, Header> {
+        #[cfg(feature = "snapshot")]
+        self.make_unique();
         ThinMut::new(self.0.ptr().cast())
     }
 
     fn is_static(&self) -> bool {
         self.capacity() == 0
     }
+    #[cfg(feature = "snapshot")]
+    fn is_shared(&self) -> bool {
+        !self.is_static() && self.header().rc.load(AtomicOrdering::Acquire) > 1
+    }
+    // Copies the items into a new allocation if any snapshots still refer to
+    // this one, so that it can be safely mutated.
+    #[cfg(feature = "snapshot")]
+    fn make_unique(&mut self) {
+        if self.is_shared() {
+            *self = self.copy_with_capacity(self.capacity());
+        }
+    }
+    // Items are snapshotted rather than cloned, so nested arrays and objects
+    // are only copied once they are themselves mutated.
+    #[cfg(feature = "snapshot")]
+    fn copy_with_capacity(&self, cap: usize) -> Self {
+        let src = self.as_slice();
+        let mut res = Self::with_capacity(cap.max(src.len()));
+        res.set_growth(self.header().growth());
+        if !src.is_empty() {
+            unsafe {
+                // Safety: we cannot be static if len > 0
+                let mut hd = res.header_mut();
+                for v in src {
+                    // Safety: we reserved enough space at the start
+                    hd.push(v.snapshot());
+                }
+            }
+        }
+        res
+    }
     /// Returns the capacity of the array. This is the maximum number of items the array
     /// can hold without reallocating.
     #[must_use]
     pub fn capacity(&self) -> usize {
-        self.header().cap
+        self.header().cap()
+    }
+
+    /// Returns the factor by which the capacity of the array is multiplied
+    /// when it has to grow. See [`IArray::with_capacity_and_growth`].
+    #[must_use]
+    pub fn growth_factor(&self) -> f64 {
+        match self.header().growth() {
+            0 => 2.0,
+            growth => 1.0 + growth as f64 / 8.0,
+        }
+    }
+    /// Returns the size in bytes of the heap allocation backing this array, including
+    /// the header. This is zero for an empty array with no capacity, which
+    /// does not allocate.
+    ///
+    /// This does not include the memory used by the items themselves.
+    #[must_use]
+    pub fn allocation_size(&self) -> usize {
+        if self.is_static() {
+            0
+        } else {
+            Self::layout(self.capacity()).unwrap().size()
+        }
     }
 
     /// Returns the number of items currently stored in the array.
@@ -188,6 +384,93 @@ impl IArray {
         self.header().items_slice()
     }
 
+    /// Returns a reference to the item at `index`, or `None` if it is out of
+    /// range. This is the same as calling `get` on the slice.
+    #[must_use]
+    pub fn try_get(&self, index: usize) -> Option<&IValue> {
+        self.as_slice().get(index)
+    }
+
+    /// Returns a reference to the item at `index`, or `default` if it is out
+    /// of range.
+    #[must_use]
+    pub fn get_or<'a>(&'a self, index: usize, default: &'a IValue) -> &'a IValue {
+        self.try_get(index).unwrap_or(default)
+    }
+
+    /// Splits the array into chunks of exactly `N` items, starting at the
+    /// front. Returns an iterator over the chunks, along with the remaining
+    /// items which did not fill a whole chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero.
+    pub fn array_chunks<const N: usize>(
+        &self,
+    ) -> (impl Iterator<Item = &[IValue; N]> + '_, &[IValue]) {
+        assert!(N != 0, "chunk size must be non-zero");
+        let chunks = self.as_slice().chunks_exact(N);
+        let remainder = chunks.remainder();
+        (
+            chunks.map(|chunk| chunk.try_into().expect("chunk has length N")),
+            remainder,
+        )
+    }
+
+    /// Splits the array into runs of consecutive items, where `pred` returns
+    /// `true` for every pair of neighbouring items within a run. Returns an
+    /// iterator over the runs, in order.
+    ///
+    /// This behaves like `slice::chunk_by`, so grouping a sorted array by
+    /// equality yields each distinct value's run of items.
+    pub fn chunk_by<'a, F>(&'a self, mut pred: F) -> impl Iterator<Item = &'a [IValue]> + 'a
+    where
+        F: FnMut(&IValue, &IValue) -> bool + 'a,
+    {
+        let mut rest = self.as_slice();
+        std::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+            let len = rest
+                .windows(2)
+                .position(|pair| !pred(&pair[0], &pair[1]))
+                .map_or(rest.len(), |i| i + 1);
+            let (chunk, tail) = rest.split_at(len);
+            rest = tail;
+            Some(chunk)
+        })
+    }
+
+    /// Joins the items of the array with `sep` into a single interned string,
+    /// if every item is a string. Returns `None` otherwise.
+    ///
+    /// An empty array produces the empty string.
+    #[must_use]
+    pub fn join_strings(&self, sep: &str) -> Option<IString> {
+        let parts = self
+            .iter()
+            .map(|v| v.as_string().map(IString::as_str))
+            .collect::<Option<Vec<_>>>()?;
+        Some(IString::intern(&parts.join(sep)))
+    }
+
+    /// Converts the array into a fixed-size array of its items, if it contains
+    /// exactly `N` items.
+    ///
+    /// # Errors
+    ///
+    /// Will return the original array if its length is not `N`.
+    pub fn try_into_fixed<const N: usize>(self) -> Result<[IValue; N], IArray> {
+        if self.len() != N {
+            return Err(self);
+        }
+        let items: Vec<IValue> = self.into_iter().collect();
+        Ok(items
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("array has length N")))
+    }
+
     /// Borrows a mutable slice of [`IValue`]s from the array
     pub fn as_mut_slice(&mut self) -> &mut [IValue] {
         if self.is_static() {
@@ -197,6 +480,11 @@ impl IArray {
         }
     }
     fn resize_internal(&mut self, cap: usize) {
+        #[cfg(feature = "snapshot")]
+        if self.is_shared() {
+            *self = self.copy_with_capacity(cap);
+            return;
+        }
         if self.is_static() || cap == 0 {
             *self = Self::with_capacity(cap);
         } else {
@@ -208,14 +496,25 @@ impl IArray {
     }
 
     /// Reserves space for at least this many additional items.
+    ///
+    /// If the array must grow, its capacity is multiplied by its
+    /// [growth factor](IArray::growth_factor) (doubled by default) or increased
+    /// to the required capacity, whichever is larger.
     pub fn reserve(&mut self, additional: usize) {
         let hd = self.header();
-        let current_capacity = hd.cap;
+        let current_capacity = hd.cap();
         let desired_capacity = hd.len.checked_add(additional).unwrap();
         if current_capacity >= desired_capacity {
             return;
         }
-        self.resize_internal(cmp::max(current_capacity * 2, desired_capacity.max(4)));
+        let eighths = match hd.growth() {
+            0 => 16,
+            growth => 8 + growth as u128,
+        };
+        let grown_capacity = (current_capacity as u128 * eighths / 8)
+            .try_into()
+            .unwrap_or(usize::MAX);
+        self.resize_internal(cmp::max(grown_capacity, desired_capacity.max(4)));
     }
 
     /// Truncates the array by removing items until it is no longer than the specified
@@ -232,6 +531,74 @@ impl IArray {
         }
     }
 
+    /// Truncates the array like [`IArray::truncate`], but returns the removed
+    /// items as a new array, in their original order.
+    #[must_use]
+    pub fn truncate_and_collect(&mut self, len: usize) -> IArray {
+        if self.len() <= len {
+            return IArray::new();
+        }
+        let mut tail = IArray::with_capacity(self.len() - len);
+        unsafe {
+            let mut src = self.header_mut();
+            let mut dst = tail.header_mut();
+            while src.len > len {
+                // Safety: the tail has space for every removed item
+                dst.push(src.pop().unwrap());
+            }
+        }
+        tail.reverse();
+        tail
+    }
+
+    /// Reverses the order of the items in the array, in place.
+    ///
+    /// Arrays with fewer than two items are left untouched, so are never
+    /// copied even if they are shared with a snapshot.
+    pub fn reverse(&mut self) {
+        if self.len() > 1 {
+            self.as_mut_slice().reverse();
+        }
+    }
+
+    /// Overwrites the items starting at index `start` with clones of `values`.
+    /// The length and capacity are unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start + values.len()` is greater than the length of the array.
+    pub fn overwrite(&mut self, start: usize, values: &[IValue]) {
+        let len = self.len();
+        let end = start
+            .checked_add(values.len())
+            .filter(|&end| end <= len)
+            .unwrap_or_else(|| {
+                panic!(
+                    "cannot overwrite {} items at index {} of an array of length {}",
+                    values.len(),
+                    start,
+                    len
+                )
+            });
+        self.as_mut_slice()[start..end].clone_from_slice(values);
+    }
+
+    /// Overwrites every item in the array with a clone of `value`. The length and
+    /// capacity are unchanged.
+    pub fn fill(&mut self, value: &IValue) {
+        for v in self.as_mut_slice() {
+            v.clone_from(value);
+        }
+    }
+
+    /// Overwrites every item in the array with the result of calling `f`. The
+    /// length and capacity are unchanged.
+    pub fn fill_with(&mut self, mut f: impl FnMut() -> IValue) {
+        for v in self.as_mut_slice() {
+            *v = f();
+        }
+    }
+
     /// Removes all items from the array. The capacity is unchanged.
     pub fn clear(&mut self) {
         self.truncate(0);
@@ -278,6 +645,54 @@ impl IArray {
         }
     }
 
+    /// Removes the items within `range` from the array, shifting any items
+    /// after the range back to close the gap in a single pass.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds, or its start is after its end,
+    /// in the same way as indexing a slice.
+    pub fn remove_range(&mut self, range: impl RangeBounds<usize>) {
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n
+                .checked_add(1)
+                .expect("attempted to index slice from after maximum usize"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n
+                .checked_add(1)
+                .expect("attempted to index slice up to maximum usize"),
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => self.len(),
+        };
+        let removed = self.as_mut_slice()[start..end].len();
+        if removed > 0 {
+            self.as_mut_slice()[start..].rotate_left(removed);
+            self.truncate(self.len() - removed);
+        }
+    }
+
+    /// Calls the specified function for each item in the array, in order, and
+    /// removes every item for which it returns `false`. Returns the number of
+    /// items which were removed.
+    ///
+    /// The function also has the ability to modify the items in-place.
+    pub fn retain_count(&mut self, mut f: impl FnMut(&mut IValue) -> bool) -> usize {
+        let len = self.len();
+        let items = self.as_mut_slice();
+        let mut kept = 0;
+        for index in 0..len {
+            if f(&mut items[index]) {
+                items.swap(kept, index);
+                kept += 1;
+            }
+        }
+        self.truncate(kept);
+        len - kept
+    }
+
     /// Removes and returns the item at the specified index from the array by
     /// first swapping it with the item currently at the end of the array, and
     /// then popping that last item.
@@ -302,10 +717,17 @@ impl IArray {
 
     /// Pushes a new item onto the back of the array.
     pub fn push(&mut self, item: impl Into<IValue>) {
+        self.push_value(item.into());
+    }
+
+    /// Pushes a new item onto the back of the array like [`IArray::push`], but
+    /// without any conversion, to avoid generating a copy of this function for
+    /// every item type.
+    pub fn push_value(&mut self, item: IValue) {
         self.reserve(1);
         // Safety: We just reserved enough space for at least one extra item
         unsafe {
-            self.header_mut().push(item.into());
+            self.header_mut().push(item);
         }
     }
 
@@ -320,12 +742,132 @@ impl IArray {
         }
     }
 
+    /// Converts the array into an iterator which yields its items in reverse
+    /// order, starting from the back.
+    ///
+    /// Unlike reversing the result of `into_iter`, this does not need to
+    /// shuffle the items first, as they are simply popped off the end.
+    #[must_use]
+    pub fn into_iter_rev(self) -> IntoIterRev {
+        IntoIterRev { array: self }
+    }
+
     /// Shrinks the memory allocation used by the array such that its
     /// capacity becomes equal to its length.
     pub fn shrink_to_fit(&mut self) {
         self.resize_internal(self.len());
     }
 
+    /// Shrinks the memory allocation used by the array such that its
+    /// capacity is at least `min_capacity`, and at least its length.
+    ///
+    /// Does nothing if the capacity is already that small.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let cap = cmp::max(self.len(), min_capacity);
+        if self.capacity() > cap {
+            self.resize_internal(cap);
+        }
+    }
+
+    /// Returns the type shared by every item of the array, or `None` if the
+    /// items have different types or the array is empty.
+    #[must_use]
+    pub fn element_type(&self) -> Option<ValueType> {
+        let (first, rest) = self.as_slice().split_first()?;
+        let type_ = first.type_();
+        if rest.iter().all(|v| v.type_() == type_) {
+            Some(type_)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the items of the array are in ascending order.
+    ///
+    /// Items are compared using the `PartialOrd` implementation of [`IValue`].
+    /// Objects cannot be ordered relative to each other, so an array with two
+    /// adjacent objects is never considered sorted.
+    #[must_use]
+    pub fn is_sorted(&self) -> bool {
+        self.is_sorted_by(|a, b| a <= b)
+    }
+
+    /// Returns `true` if `f` returns `true` for every pair of adjacent items in
+    /// the array, ie. if the items are sorted according to `f`.
+    pub fn is_sorted_by(&self, mut f: impl FnMut(&IValue, &IValue) -> bool) -> bool {
+        self.as_slice().windows(2).all(|w| f(&w[0], &w[1]))
+    }
+
+    /// Returns the index of the first item for which `pred` returns `false`,
+    /// assuming that the array is partitioned such that `pred` returns `true`
+    /// for all items before that point, and `false` for all items after it.
+    ///
+    /// This is useful for finding where to insert an item into a sorted array.
+    /// See `slice::partition_point` for details.
+    pub fn partition_point(&self, pred: impl FnMut(&IValue) -> bool) -> usize {
+        self.as_slice().partition_point(pred)
+    }
+
+    /// Removes all duplicate items from the array, keeping the first occurrence
+    /// of each. Unlike `Vec::dedup`, duplicates do not need to be adjacent.
+    ///
+    /// Items are compared using their `Hash` and `Eq` implementations, so interned
+    /// strings are compared cheaply. Objects compare equal regardless of the order
+    /// of their entries, so arrays of objects are deduplicated as expected.
+    pub fn unique(&mut self) {
+        let keep: Vec<bool> = {
+            let mut seen = HashSet::with_capacity(self.len());
+            self.iter().map(|item| seen.insert(item)).collect()
+        };
+        if keep.iter().all(|&k| k) {
+            return;
+        }
+
+        let items = self.as_mut_slice();
+        let mut len = 0;
+        for (index, keep) in keep.into_iter().enumerate() {
+            if keep {
+                items.swap(len, index);
+                len += 1;
+            }
+        }
+        self.truncate(len);
+    }
+
+    /// Returns a cheap, structurally-shared snapshot of this array.
+    ///
+    /// Rather than copying the items, the snapshot shares the original
+    /// allocation, which is reference counted. Whichever of the two is mutated
+    /// first will copy the items out into a new allocation (copy-on-write), so
+    /// the snapshot is unaffected by later changes to the original and vice
+    /// versa. Nested arrays and objects are shared in the same way.
+    ///
+    /// Snapshots may be sent to other threads and read concurrently with the
+    /// original.
+    ///
+    /// Requires the `snapshot` feature, which adds a reference count to every
+    /// array and object allocation.
+    #[cfg(feature = "snapshot")]
+    #[must_use]
+    pub fn snapshot(&self) -> Self {
+        if !self.is_static() {
+            self.header().rc.fetch_add(1, AtomicOrdering::Relaxed);
+        }
+        IArray(unsafe { self.0.raw_copy() })
+    }
+
+    // Existing items are overwritten in place, so that any allocations they
+    // own can be reused.
+    pub(crate) fn clone_from_impl(&mut self, source: &IArray) {
+        let src = source.as_slice();
+        self.truncate(src.len());
+        let (init, tail) = src.split_at(self.len());
+        for (dest, v) in self.as_mut_slice().iter_mut().zip(init) {
+            dest.clone_from(v);
+        }
+        self.extend(tail.iter().cloned());
+    }
+
     pub(crate) fn clone_impl(&self) -> IValue {
         let src = self.header().items_slice();
         let l = src.len();
@@ -344,6 +886,15 @@ impl IArray {
         res.0
     }
     pub(crate) fn drop_impl(&mut self) {
+        #[cfg(feature = "snapshot")]
+        if !self.is_static() {
+            if self.header().rc.fetch_sub(1, AtomicOrdering::Release) != 1 {
+                // Other snapshots still refer to this allocation
+                unsafe { self.0.set_ref(&EMPTY_HEADER) };
+                return;
+            }
+            fence(AtomicOrdering::Acquire);
+        }
         self.clear();
         if !self.is_static() {
             unsafe {
@@ -586,6 +1137,114 @@ mod tests {
         assert_eq!(x.capacity(), 2);
     }
 
+    #[mockalloc::test]
+    fn can_split_into_array_chunks() {
+        let x: IArray = vec![1, 2, 3, 4, 5].into();
+        let (chunks, remainder) = x.array_chunks::<2>();
+        let chunks: Vec<_> = chunks.collect();
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0], &[IValue::from(1), IValue::from(2)]);
+        assert_eq!(chunks[1], &[IValue::from(3), IValue::from(4)]);
+        assert_eq!(remainder, &[IValue::from(5)]);
+    }
+
+    #[mockalloc::test]
+    fn can_into_iter_rev() {
+        let x: IArray = vec![1, 2, 3].into();
+        let mut iter = x.into_iter_rev();
+
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next(), Some(IValue::from(3)));
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.size_hint(), (2, Some(2)));
+        assert_eq!(iter.next(), Some(IValue::from(2)));
+        assert_eq!(iter.next(), Some(IValue::from(1)));
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[mockalloc::test]
+    fn can_check_sorted() {
+        let x: IArray = vec![1, 2, 2, 5].into();
+        let y: IArray = vec![1, 3, 2].into();
+        let z: IArray = ijson!([{}, {}]).into_array().unwrap();
+
+        assert!(x.is_sorted());
+        assert!(!y.is_sorted());
+        assert!(!z.is_sorted());
+        assert!(IArray::new().is_sorted());
+        assert!(y.is_sorted_by(|a, b| a.is_number() && b.is_number()));
+    }
+
+    #[mockalloc::test]
+    fn can_insert_at_partition_point() {
+        let mut x: IArray = vec![1, 3, 5, 7].into();
+        let item = IValue::from(4);
+        let index = x.partition_point(|v| *v < item);
+        x.insert(index, item);
+
+        assert_eq!(index, 2);
+        assert_eq!(x, IArray::from(vec![1, 3, 4, 5, 7]));
+    }
+
+    #[mockalloc::test]
+    fn can_unique() {
+        let mut x: IArray = vec![1, 2, 1, 3, 2].into();
+        x.unique();
+
+        assert_eq!(x, IArray::from(vec![1, 2, 3]));
+    }
+
+    #[mockalloc::test]
+    fn can_unique_nested_objects() {
+        let mut x: IArray = ijson!([
+            {"a": 1, "b": [true]},
+            {"a": 2},
+            {"b": [true], "a": 1},
+            {"a": 2}
+        ])
+        .into_array()
+        .unwrap();
+        x.unique();
+
+        assert_eq!(x.len(), 2);
+        assert_eq!(x[0], ijson!({"a": 1, "b": [true]}));
+        assert_eq!(x[1], ijson!({"a": 2}));
+    }
+
+    #[cfg(feature = "snapshot")]
+    #[mockalloc::test]
+    fn snapshot_is_independent() {
+        let mut x: IArray = vec![IValue::NULL, IValue::TRUE].into();
+        let y = x.snapshot();
+        assert_eq!(x.as_slice().as_ptr(), y.as_slice().as_ptr());
+
+        x.as_mut_slice()[0] = IValue::FALSE;
+        x.insert(0, IValue::NULL);
+        x.push(IValue::TRUE);
+
+        assert_eq!(
+            x.as_slice(),
+            &[IValue::NULL, IValue::FALSE, IValue::TRUE, IValue::TRUE]
+        );
+        assert_eq!(y.as_slice(), &[IValue::NULL, IValue::TRUE]);
+    }
+
+    #[cfg(feature = "snapshot")]
+    #[mockalloc::test]
+    fn snapshot_shares_nested_arrays() {
+        let inner: IArray = vec![IValue::NULL].into();
+        let mut x: IArray = vec![IValue::from(inner)].into();
+        let y = x.snapshot();
+
+        x[0].as_array_mut().unwrap().push(IValue::TRUE);
+        x.truncate(0);
+        drop(x);
+
+        assert_eq!(y[0].as_array().unwrap().as_slice(), &[IValue::NULL]);
+    }
+
     // Too slow for miri
     #[cfg(not(miri))]
     #[mockalloc::test]
@@ -607,4 +1266,310 @@ mod tests {
             }
         }
     }
+
+    #[mockalloc::test]
+    fn can_get_allocation_size() {
+        assert_eq!(IArray::new().allocation_size(), 0);
+        for cap in [1, 4, 17].iter().copied() {
+            let x = IArray::with_capacity(cap);
+            assert_eq!(x.allocation_size(), IArray::layout(cap).unwrap().size());
+            assert!(
+                x.allocation_size()
+                    >= std::mem::size_of::<Header>() + cap * std::mem::size_of::<IValue>()
+            );
+        }
+    }
+
+    #[mockalloc::test]
+    fn can_repeat() {
+        let value: IValue = ijson!({"a": [1, 2]});
+        let mut x = IArray::repeat(&value, 5);
+
+        assert_eq!(x.len(), 5);
+        assert_eq!(x.capacity(), 5);
+        assert!(x.iter().all(|v| *v == value));
+
+        x[0]["a"].as_array_mut().unwrap().push(3);
+        assert_eq!(x[0], ijson!({"a": [1, 2, 3]}));
+        assert!(x[1..].iter().all(|v| *v == value));
+        assert!(IArray::repeat(&value, 0).is_empty());
+    }
+
+    #[mockalloc::test]
+    fn can_convert_into_fixed() {
+        let x: IArray = ijson!([1.5, "a", null]).into_array().unwrap();
+        let [a, b, c] = x.try_into_fixed::<3>().unwrap();
+        assert_eq!(a, IValue::from(1.5));
+        assert_eq!(b, IValue::from("a"));
+        assert!(c.is_null());
+
+        let x: IArray = ijson!([1, 2]).into_array().unwrap();
+        let x = x.try_into_fixed::<3>().unwrap_err();
+        assert_eq!(IValue::from(x), ijson!([1, 2]));
+        let empty: [IValue; 0] = IArray::new().try_into_fixed().unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn can_map() {
+        // Small integers are statically allocated, so only the array allocates
+        let x: IArray = ijson!([1, 2, 3]).into_array().unwrap();
+        let mut y = None;
+        let info = mockalloc::record_allocs(|| {
+            y = Some(x.map(|v| (v.to_i64().unwrap() * 2).into()));
+        });
+
+        assert_eq!(info.num_allocs(), 1);
+        assert_eq!(y.unwrap(), ijson!([2, 4, 6]).into_array().unwrap());
+        assert!(IArray::new().map(|v| v.clone()).is_empty());
+    }
+
+    #[test]
+    fn can_fill() {
+        let mut x: IArray = ijson!([1, "a", null]).into_array().unwrap();
+        let ptr = x.as_ptr();
+        let info = mockalloc::record_allocs(|| {
+            x.fill(&IValue::TRUE);
+        });
+
+        assert_eq!(info.num_allocs(), 0);
+        assert_eq!(x.as_ptr(), ptr);
+        assert_eq!(x, ijson!([true, true, true]).into_array().unwrap());
+
+        let mut i = 0;
+        x.fill_with(|| {
+            i += 1;
+            i.into()
+        });
+        assert_eq!(x, ijson!([1, 2, 3]).into_array().unwrap());
+        assert_eq!(x.as_ptr(), ptr);
+    }
+
+    #[mockalloc::test]
+    fn can_get_element_type() {
+        let element_type = |v: IValue| v.into_array().unwrap().element_type();
+
+        assert_eq!(element_type(ijson!([1, 2.5, -3])), Some(ValueType::Number));
+        assert_eq!(element_type(ijson!(["a", "b"])), Some(ValueType::String));
+        assert_eq!(element_type(ijson!([true, false])), Some(ValueType::Bool));
+        assert_eq!(element_type(ijson!([1, "a"])), None);
+        assert_eq!(element_type(ijson!([null, {}])), None);
+        assert_eq!(IArray::new().element_type(), None);
+    }
+
+    #[mockalloc::test]
+    fn can_set_growth_factor() {
+        let capacities = |mut x: IArray| {
+            let mut res = vec![x.capacity()];
+            for i in 0..20 {
+                x.push(i);
+                if res.last() != Some(&x.capacity()) {
+                    res.push(x.capacity());
+                }
+            }
+            x.extend(0..20);
+            res.push(x.capacity());
+            res
+        };
+
+        assert_eq!(capacities(IArray::new()), [0, 4, 8, 16, 32, 64]);
+        assert_eq!(
+            capacities(IArray::with_capacity_and_growth(4, 2.0)),
+            [4, 8, 16, 32, 64]
+        );
+        assert_eq!(
+            capacities(IArray::with_capacity_and_growth(4, 1.5)),
+            [4, 6, 9, 13, 19, 28, 42]
+        );
+        assert_eq!(
+            capacities(IArray::with_capacity_and_growth(0, 1.2)),
+            [1, 4, 5, 6, 7, 8, 10, 12, 15, 18, 22, 40]
+        );
+
+        assert_eq!(IArray::new().growth_factor(), 2.0);
+        assert_eq!(
+            IArray::with_capacity_and_growth(1, 1.3).growth_factor(),
+            1.25
+        );
+        let mut x = IArray::with_capacity_and_growth(1, 1.5);
+        x.extend(0..100);
+        x.shrink_to_fit();
+        assert_eq!(x.growth_factor(), 1.5);
+    }
+
+    #[test]
+    #[should_panic(expected = "growth factor must be greater than 1.0")]
+    fn growth_factor_rejects_shrinking() {
+        let _ = IArray::with_capacity_and_growth(1, 1.0);
+    }
+
+    #[mockalloc::test]
+    fn can_chunk_by() {
+        let x: IArray = vec![1, 1, 2, 3, 3, 3].into();
+        let runs: Vec<_> = x.chunk_by(|a, b| a == b).collect();
+
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[0], [IValue::from(1), IValue::from(1)]);
+        assert_eq!(runs[1], [IValue::from(2)]);
+        assert_eq!(runs[2], [IValue::from(3), IValue::from(3), IValue::from(3)]);
+        assert_eq!(IArray::new().chunk_by(|_, _| true).count(), 0);
+    }
+
+    #[mockalloc::test]
+    fn can_truncate_and_collect() {
+        let mut x: IArray = vec![1, 2, 3, 4, 5].into();
+        let tail = x.truncate_and_collect(3);
+
+        assert_eq!(x, IArray::from(vec![1, 2, 3]));
+        assert_eq!(tail, IArray::from(vec![4, 5]));
+        assert!(x.truncate_and_collect(3).is_empty());
+        assert_eq!(x.len(), 3);
+    }
+
+    #[mockalloc::test]
+    fn can_join_strings() {
+        let x: IArray = vec!["a", "b", "c"].into();
+        let y: IArray = vec![IValue::from("a"), IValue::from(1)].into();
+
+        assert_eq!(x.join_strings(", ").unwrap().as_str(), "a, b, c");
+        assert!(y.join_strings(", ").is_none());
+        assert_eq!(IArray::new().join_strings(", "), Some(IString::new()));
+    }
+
+    #[mockalloc::test]
+    fn can_shrink_to() {
+        let mut x = IArray::with_capacity(20);
+        x.extend(0..5);
+        x.shrink_to(10);
+        assert_eq!(x.capacity(), 10);
+        x.shrink_to(15);
+        assert_eq!(x.capacity(), 10);
+        x.shrink_to(0);
+        assert_eq!(x.capacity(), 5);
+        assert_eq!(x.len(), 5);
+    }
+
+    #[mockalloc::test]
+    fn can_remove_range() {
+        let mut x: IArray = (0..1000).collect();
+        x.remove_range(100..900);
+
+        assert_eq!(x.len(), 200);
+        assert_eq!(x.capacity(), 1000);
+        let expected: IArray = (0..100).chain(900..1000).collect();
+        assert_eq!(x, expected);
+
+        x.remove_range(..=49);
+        x.remove_range(100..);
+        x.remove_range(50..50);
+        assert_eq!(x, (50..100).chain(900..950).collect::<IArray>());
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn remove_range_checks_bounds() {
+        let mut x: IArray = (0..5).collect();
+        x.remove_range(3..6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_range_checks_order() {
+        let mut x: IArray = (0..5).collect();
+        #[allow(clippy::reversed_empty_ranges)]
+        x.remove_range(3..2);
+    }
+
+    #[mockalloc::test]
+    fn can_count_retained() {
+        let mut x: IArray = (0..10).collect();
+
+        assert_eq!(x.retain_count(|v| v.to_i32().unwrap() % 3 == 0), 6);
+        assert_eq!(x, IArray::from(vec![0, 3, 6, 9]));
+        assert_eq!(x.retain_count(|_| true), 0);
+        assert_eq!(IArray::new().retain_count(|_| false), 0);
+    }
+
+    #[mockalloc::test]
+    fn can_push_value() {
+        let mut x = IArray::new();
+        let mut y = IArray::new();
+        for i in 0..10 {
+            x.push(i);
+            y.push_value(i.into());
+        }
+        x.push("a");
+        y.push_value("a".into());
+
+        assert_eq!(x, y);
+    }
+
+    #[mockalloc::test]
+    fn can_overwrite() {
+        let mut x: IArray = (0..6).collect();
+        x.overwrite(2, &[IValue::from("a"), IValue::from("b"), IValue::NULL]);
+
+        assert_eq!(
+            x,
+            IArray::from(vec![
+                IValue::from(0),
+                IValue::from(1),
+                IValue::from("a"),
+                IValue::from("b"),
+                IValue::NULL,
+                IValue::from(5),
+            ])
+        );
+        x.overwrite(6, &[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot overwrite 2 items at index 5 of an array of length 6")]
+    fn overwrite_never_grows() {
+        let mut x: IArray = (0..6).collect();
+        x.overwrite(5, &[IValue::NULL, IValue::NULL]);
+    }
+
+    #[mockalloc::test]
+    fn can_try_from_iter() {
+        let ok: Result<IArray, &str> = IArray::try_from_iter(vec![Ok(1), Ok(2)]);
+        assert_eq!(ok, Ok(IArray::from(vec![1, 2])));
+
+        let mut consumed = 0;
+        let items = (0..5).map(|i| {
+            consumed += 1;
+            if i == 2 {
+                Err(format!("bad item {}", i))
+            } else {
+                Ok(i)
+            }
+        });
+        assert_eq!(IArray::try_from_iter(items), Err("bad item 2".to_owned()));
+        assert_eq!(consumed, 3);
+    }
+
+    #[mockalloc::test]
+    fn can_reverse() {
+        let mut x: IArray = (1..=4).collect();
+        x.reverse();
+        assert_eq!(x, (1..=4).rev().collect::<IArray>());
+
+        let mut y = IArray::new();
+        y.reverse();
+        assert!(y.is_empty());
+    }
+
+    #[mockalloc::test]
+    fn can_try_get() {
+        let x: IArray = vec![1, 2, 3].into();
+        let default = IValue::from("default");
+
+        assert_eq!(x.try_get(0), Some(&IValue::from(1)));
+        assert_eq!(x.try_get(2), Some(&IValue::from(3)));
+        assert_eq!(x.try_get(3), None);
+        assert_eq!(IArray::new().try_get(0), None);
+        assert_eq!(x.get_or(1, &default), &IValue::from(2));
+        assert_eq!(x.get_or(3, &default), &default);
+        assert_eq!(x.get_or(usize::MAX, &default), &default);
+    }
 }
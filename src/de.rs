@@ -1,5 +1,7 @@
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt::{self, Formatter};
+use std::io::{self, Read};
 use std::slice;
 
 use serde::de::{
@@ -11,8 +13,12 @@ use serde_json::error::Error;
 
 use super::array::IArray;
 use super::number::INumber;
+#[cfg(feature = "arbitrary_precision")]
+use super::number::NUMBER_TOKEN;
 use super::object::IObject;
-use super::string::IString;
+#[cfg(feature = "direct_strings")]
+use super::string::TooLong;
+use super::string::{init_cache, IString};
 use super::value::{DestructuredRef, IValue};
 
 impl<'de> Deserialize<'de> for IValue {
@@ -20,7 +26,7 @@ impl<'de> Deserialize<'de> for IValue {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_any(ValueVisitor)
+        DeserializeLimits::new().deserialize(deserializer)
     }
 }
 
@@ -38,7 +44,7 @@ impl<'de> Deserialize<'de> for IString {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_str(StringVisitor)
+        deserializer.deserialize_str(StringVisitor(DeserializeLimits::new()))
     }
 }
 
@@ -47,7 +53,7 @@ impl<'de> Deserialize<'de> for IArray {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_seq(ArrayVisitor)
+        deserializer.deserialize_seq(ArrayVisitor(DeserializeLimits::new()))
     }
 }
 
@@ -56,11 +62,106 @@ impl<'de> Deserialize<'de> for IObject {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_map(ObjectVisitor)
+        deserializer.deserialize_map(ObjectVisitor(DeserializeLimits::new()))
     }
 }
 
-struct ValueVisitor;
+/// Limits which are enforced while deserializing an [`IValue`] from untrusted
+/// input.
+///
+/// This implements [`DeserializeSeed`], so it can be passed to any
+/// deserializer in place of `IValue::deserialize`:
+///
+/// ```
+/// use ijson::DeserializeLimits;
+/// use serde::de::DeserializeSeed;
+///
+/// let limits = DeserializeLimits::new().max_string_len(3);
+/// let mut de = serde_json::Deserializer::from_str(r#"["foo", "food"]"#);
+/// assert!(limits.deserialize(&mut de).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeserializeLimits {
+    max_string_len: usize,
+    #[cfg(feature = "direct_strings")]
+    direct_strings: bool,
+}
+
+impl DeserializeLimits {
+    /// Constructs a new set of limits, which initially allow any input.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            max_string_len: usize::MAX,
+            #[cfg(feature = "direct_strings")]
+            direct_strings: false,
+        }
+    }
+    /// Rejects any string or object key longer than `max_len` bytes, rather
+    /// than interning it. See [`IString::try_intern`].
+    #[must_use]
+    pub fn max_string_len(mut self, max_len: usize) -> Self {
+        self.max_string_len = max_len;
+        self
+    }
+    /// Stores string values without interning them if `direct` is `true`.
+    /// Object keys are still interned. See [`IString::direct`].
+    ///
+    /// Requires the `direct_strings` feature.
+    #[cfg(feature = "direct_strings")]
+    #[must_use]
+    pub fn direct_strings(mut self, direct: bool) -> Self {
+        self.direct_strings = direct;
+        self
+    }
+}
+
+impl Default for DeserializeLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for DeserializeLimits {
+    type Value = IValue;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<IValue, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor(self))
+    }
+}
+
+// Deserializes an object key, subject to the limits.
+struct KeySeed(DeserializeLimits);
+
+impl<'de> DeserializeSeed<'de> for KeySeed {
+    type Value = IString;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<IString, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(StringVisitor(self.0))
+    }
+}
+
+fn intern_limited<E: SError>(value: &str, limits: DeserializeLimits) -> Result<IString, E> {
+    IString::try_intern(value, limits.max_string_len).map_err(E::custom)
+}
+
+// Like `intern_limited`, but for string values rather than object keys.
+fn string_limited<E: SError>(value: &str, limits: DeserializeLimits) -> Result<IString, E> {
+    #[cfg(feature = "direct_strings")]
+    if limits.direct_strings {
+        TooLong::check(value, limits.max_string_len).map_err(E::custom)?;
+        return Ok(IString::direct(value));
+    }
+    intern_limited(value, limits)
+}
+
+struct ValueVisitor(DeserializeLimits);
 
 impl<'de> Visitor<'de> for ValueVisitor {
     type Value = IValue;
@@ -86,17 +187,19 @@ impl<'de> Visitor<'de> for ValueVisitor {
 
     #[inline]
     fn visit_f64<E: SError>(self, value: f64) -> Result<IValue, E> {
-        Ok(value.into())
+        INumber::try_from(value)
+            .map(Into::into)
+            .map_err(|_| E::invalid_value(Unexpected::Float(value), &self))
     }
 
     #[inline]
     fn visit_str<E: SError>(self, value: &str) -> Result<IValue, E> {
-        Ok(value.into())
+        string_limited(value, self.0).map(Into::into)
     }
 
     #[inline]
     fn visit_string<E: SError>(self, value: String) -> Result<IValue, E> {
-        Ok(value.into())
+        string_limited(&value, self.0).map(Into::into)
     }
 
     #[inline]
@@ -109,7 +212,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         D: Deserializer<'de>,
     {
-        Deserialize::deserialize(deserializer)
+        self.0.deserialize(deserializer)
     }
 
     #[inline]
@@ -122,20 +225,100 @@ impl<'de> Visitor<'de> for ValueVisitor {
     where
         V: SeqAccess<'de>,
     {
-        ArrayVisitor.visit_seq(visitor).map(Into::into)
+        ArrayVisitor(self.0).visit_seq(visitor).map(Into::into)
     }
 
+    #[cfg(not(feature = "arbitrary_precision"))]
     fn visit_map<V>(self, visitor: V) -> Result<IValue, V::Error>
     where
         V: MapAccess<'de>,
     {
-        ObjectVisitor.visit_map(visitor).map(Into::into)
+        ObjectVisitor(self.0).visit_map(visitor).map(Into::into)
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_map<V>(self, mut visitor: V) -> Result<IValue, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        let mut obj = IObject::with_capacity(visitor.size_hint().unwrap_or(0));
+        match visitor.next_key_seed(KeyClassifier(self.0))? {
+            Some(KeyClass::Number) => return visit_number_text(visitor, &self).map(Into::into),
+            Some(KeyClass::Key(k)) => {
+                obj.insert(k, visitor.next_value_seed(self.0)?);
+            }
+            None => {}
+        }
+        while let Some((k, v)) = visitor.next_entry_seed(KeySeed(self.0), self.0)? {
+            obj.insert(k, v);
+        }
+        Ok(obj.into())
+    }
+}
+
+// When `serde_json`'s `arbitrary_precision` feature is enabled, numbers are
+// passed to `visit_map` as a map with a single `NUMBER_TOKEN` key, whose value
+// is the text of the number.
+#[cfg(feature = "arbitrary_precision")]
+enum KeyClass {
+    Number,
+    Key(IString),
+}
+
+#[cfg(feature = "arbitrary_precision")]
+struct KeyClassifier(DeserializeLimits);
+
+#[cfg(feature = "arbitrary_precision")]
+impl<'de> DeserializeSeed<'de> for KeyClassifier {
+    type Value = KeyClass;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<KeyClass, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
     }
 }
 
+#[cfg(feature = "arbitrary_precision")]
+impl Visitor<'_> for KeyClassifier {
+    type Value = KeyClass;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str("a string key")
+    }
+
+    fn visit_str<E: SError>(self, value: &str) -> Result<KeyClass, E> {
+        if value == NUMBER_TOKEN {
+            Ok(KeyClass::Number)
+        } else {
+            intern_limited(value, self.0).map(KeyClass::Key)
+        }
+    }
+
+    fn visit_string<E: SError>(self, value: String) -> Result<KeyClass, E> {
+        if value == NUMBER_TOKEN {
+            Ok(KeyClass::Number)
+        } else {
+            intern_limited(&value, self.0).map(KeyClass::Key)
+        }
+    }
+}
+
+// Reads the text of a number once its `NUMBER_TOKEN` key has been seen.
+#[cfg(feature = "arbitrary_precision")]
+fn visit_number_text<'de, V>(mut visitor: V, expected: &dyn Expected) -> Result<INumber, V::Error>
+where
+    V: MapAccess<'de>,
+{
+    let text: String = visitor.next_value()?;
+    INumber::from_json_text(&text)
+        .ok_or_else(|| SError::invalid_value(Unexpected::Str(&text), expected))
+}
+
 struct NumberVisitor;
 
-impl Visitor<'_> for NumberVisitor {
+impl<'de> Visitor<'de> for NumberVisitor {
     type Value = INumber;
 
     fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
@@ -156,9 +339,20 @@ impl Visitor<'_> for NumberVisitor {
     fn visit_f64<E: SError>(self, value: f64) -> Result<INumber, E> {
         INumber::try_from(value).map_err(|_| E::invalid_value(Unexpected::Float(value), &self))
     }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_map<V>(self, mut visitor: V) -> Result<INumber, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        match visitor.next_key_seed(KeyClassifier(DeserializeLimits::new()))? {
+            Some(KeyClass::Number) => visit_number_text(visitor, &self),
+            _ => Err(SError::invalid_type(Unexpected::Map, &self)),
+        }
+    }
 }
 
-struct StringVisitor;
+struct StringVisitor(DeserializeLimits);
 
 impl Visitor<'_> for StringVisitor {
     type Value = IString;
@@ -169,18 +363,18 @@ impl Visitor<'_> for StringVisitor {
 
     #[inline]
     fn visit_str<E: SError>(self, value: &str) -> Result<IString, E> {
-        Ok(value.into())
+        intern_limited(value, self.0)
     }
 
     #[inline]
     fn visit_string<E: SError>(self, value: String) -> Result<Self::Value, E> {
-        Ok(value.into())
+        intern_limited(&value, self.0)
     }
 
     #[inline]
     fn visit_bytes<E: SError>(self, value: &[u8]) -> Result<Self::Value, E> {
         match std::str::from_utf8(value) {
-            Ok(s) => Ok(s.into()),
+            Ok(s) => intern_limited(s, self.0),
             Err(_) => Err(SError::invalid_value(Unexpected::Bytes(value), &self)),
         }
     }
@@ -188,7 +382,7 @@ impl Visitor<'_> for StringVisitor {
     #[inline]
     fn visit_byte_buf<E: SError>(self, value: Vec<u8>) -> Result<Self::Value, E> {
         match String::from_utf8(value) {
-            Ok(s) => Ok(s.into()),
+            Ok(s) => intern_limited(&s, self.0),
             Err(e) => Err(SError::invalid_value(
                 Unexpected::Bytes(&e.into_bytes()),
                 &self,
@@ -197,7 +391,7 @@ impl Visitor<'_> for StringVisitor {
     }
 }
 
-struct ArrayVisitor;
+struct ArrayVisitor(DeserializeLimits);
 
 impl<'de> Visitor<'de> for ArrayVisitor {
     type Value = IArray;
@@ -212,14 +406,14 @@ impl<'de> Visitor<'de> for ArrayVisitor {
         V: SeqAccess<'de>,
     {
         let mut arr = IArray::with_capacity(visitor.size_hint().unwrap_or(0));
-        while let Some(v) = visitor.next_element::<IValue>()? {
+        while let Some(v) = visitor.next_element_seed(self.0)? {
             arr.push(v);
         }
         Ok(arr)
     }
 }
 
-struct ObjectVisitor;
+struct ObjectVisitor(DeserializeLimits);
 
 impl<'de> Visitor<'de> for ObjectVisitor {
     type Value = IObject;
@@ -233,7 +427,7 @@ impl<'de> Visitor<'de> for ObjectVisitor {
         V: MapAccess<'de>,
     {
         let mut obj = IObject::with_capacity(visitor.size_hint().unwrap_or(0));
-        while let Some((k, v)) = visitor.next_entry::<IString, IValue>()? {
+        while let Some((k, v)) = visitor.next_entry_seed(KeySeed(self.0), self.0)? {
             obj.insert(k, v);
         }
         Ok(obj)
@@ -477,6 +671,12 @@ impl<'de> Deserializer<'de> for &'de INumber {
     where
         V: Visitor<'de>,
     {
+        #[cfg(feature = "arbitrary_precision")]
+        if let Some(text) = self.source_text() {
+            // Let `serde_json` present the number in its usual form
+            let number: serde_json::Number = text.parse()?;
+            return number.deserialize_any(visitor);
+        }
         if self.has_decimal_point() {
             visitor.visit_f64(self.to_f64().unwrap())
         } else if let Some(v) = self.to_i64() {
@@ -685,6 +885,10 @@ impl<'de> MaybeUnexpected<'de> for DestructuredRef<'de> {
 
 impl<'de> MaybeUnexpected<'de> for &'de INumber {
     fn unexpected(self) -> Unexpected<'de> {
+        #[cfg(feature = "arbitrary_precision")]
+        if let Some(text) = self.source_text() {
+            return Unexpected::Other(text);
+        }
         if self.has_decimal_point() {
             Unexpected::Float(self.to_f64().unwrap())
         } else if let Some(v) = self.to_i64() {
@@ -893,3 +1097,287 @@ where
 {
     T::deserialize(value)
 }
+
+impl IValue {
+    /// Converts this array into a `Vec` of an arbitrary type, using that type's
+    /// [`serde::Deserialize`] implementation. This is equivalent to calling
+    /// [`from_value`] with a `Vec<T>`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Error` if this is not an array, or if any item fails to
+    /// deserialize.
+    pub fn to_vec<'de, T: Deserialize<'de>>(&'de self) -> Result<Vec<T>, Error> {
+        from_value(self)
+    }
+    /// Converts this object into a `HashMap` from its keys to an arbitrary
+    /// type, using that type's [`serde::Deserialize`] implementation. This is
+    /// equivalent to calling [`from_value`] with a `HashMap<String, V>`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Error` if this is not an object, or if any value fails to
+    /// deserialize.
+    pub fn to_map<'de, V: Deserialize<'de>>(&'de self) -> Result<HashMap<String, V>, Error> {
+        from_value(self)
+    }
+}
+
+/// Parses an [`IValue`] from an IO stream of JSON.
+///
+/// This is a convenience wrapper around [`serde_json::from_reader`]. As with
+/// that function, `reader` is not buffered, so wrapping it in a
+/// [`std::io::BufReader`] is recommended.
+///
+/// # Errors
+///
+/// Will return `Error` if the input cannot be read or is not valid JSON.
+pub fn from_reader<R: Read>(reader: R) -> Result<IValue, Error> {
+    init_cache();
+    serde_json::from_reader(reader)
+}
+
+// Reads from `inner`, failing once more than `limit` bytes have been read.
+struct CappedReader<R> {
+    inner: R,
+    limit: usize,
+    remaining: usize,
+    exceeded: bool,
+}
+
+impl<R: Read> Read for CappedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // `serde_json` may keep reading after an error, so once the limit has
+        // been exceeded, no more input is consumed.
+        if !self.exceeded {
+            // Allow one byte beyond the limit, so that input of exactly `limit`
+            // bytes can be told apart from input which is too long.
+            let max = buf.len().min(self.remaining.saturating_add(1));
+            let n = self.inner.read(&mut buf[..max])?;
+            if n <= self.remaining {
+                self.remaining -= n;
+                return Ok(n);
+            }
+            self.exceeded = true;
+        }
+        Err(io::Error::other(format!(
+            "input exceeds the limit of {} bytes",
+            self.limit
+        )))
+    }
+}
+
+/// Parses an [`IValue`] from an IO stream of JSON, failing as soon as more
+/// than `max_bytes` bytes have been read.
+///
+/// This bounds the memory used when parsing untrusted input, without having
+/// to read the whole input up front. Any trailing whitespace counts towards
+/// the limit. See [`from_reader`] for details of how `reader` is used.
+///
+/// # Errors
+///
+/// Will return `Error` if the input cannot be read, is longer than
+/// `max_bytes`, or is not valid JSON. Exceeding the limit is reported as an
+/// IO error.
+pub fn from_reader_capped<R: Read>(reader: R, max_bytes: usize) -> Result<IValue, Error> {
+    from_reader(CappedReader {
+        inner: reader,
+        limit: max_bytes,
+        remaining: max_bytes,
+        exceeded: false,
+    })
+}
+
+/// Parses an [`IValue`] from a slice of bytes containing JSON.
+///
+/// # Errors
+///
+/// Will return `Error` if the input is not valid JSON.
+pub fn from_slice(slice: &[u8]) -> Result<IValue, Error> {
+    init_cache();
+    serde_json::from_slice(slice)
+}
+
+/// Parses an [`IObject`] from a string of JSON.
+///
+/// # Errors
+///
+/// Will return `Error` if the input is not valid JSON, or is not an object.
+pub fn parse_object(s: &str) -> Result<IObject, Error> {
+    init_cache();
+    serde_json::from_str(s)
+}
+
+/// Parses an [`IArray`] from a string of JSON.
+///
+/// # Errors
+///
+/// Will return `Error` if the input is not valid JSON, or is not an array.
+pub fn parse_array(s: &str) -> Result<IArray, Error> {
+    init_cache();
+    serde_json::from_str(s)
+}
+
+/// Parses newline-delimited JSON into an [`IArray`] with one item per value.
+///
+/// Blank lines are skipped. Values are read using `serde_json`'s
+/// [`StreamDeserializer`](serde_json::StreamDeserializer), so any whitespace
+/// between them is accepted, not just newlines.
+///
+/// # Errors
+///
+/// Will return `Error` if any value is not valid JSON. The line and column of
+/// the error are relative to the start of `s`.
+pub fn from_ndjson(s: &str) -> Result<IArray, Error> {
+    init_cache();
+    serde_json::Deserializer::from_str(s)
+        .into_iter::<IValue>()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_non_finite_floats() {
+        for v in [f64::INFINITY, f64::NEG_INFINITY, f64::NAN].iter().copied() {
+            let deserializer: serde::de::value::F64Deserializer<serde::de::value::Error> =
+                v.into_deserializer();
+            let err = IValue::deserialize(deserializer).unwrap_err();
+            assert!(err.to_string().starts_with("invalid value: floating point"));
+        }
+    }
+
+    #[mockalloc::test]
+    fn can_parse_from_reader() {
+        let reader = std::io::Cursor::new(r#"{"a": [1, "b", null]}"#);
+        let x = from_reader(reader).unwrap();
+
+        assert_eq!(x, ijson!({"a": [1, "b", null]}));
+        assert!(from_reader(std::io::Cursor::new("[1,")).is_err());
+    }
+
+    #[mockalloc::test]
+    fn can_cap_reader() {
+        let text = r#"{"a": [1, "b", null]}"#;
+        let x = from_reader_capped(std::io::Cursor::new(text), 100).unwrap();
+        assert_eq!(x, ijson!({"a": [1, "b", null]}));
+        let x = from_reader_capped(std::io::Cursor::new(text), text.len()).unwrap();
+        assert_eq!(x, ijson!({"a": [1, "b", null]}));
+
+        let long = format!("[{}1]", "1, ".repeat(1000));
+        let mut reader = std::io::Cursor::new(long.as_bytes());
+        let err = from_reader_capped(&mut reader, 100).unwrap_err();
+        assert!(err.is_io());
+        assert!(err.to_string().contains("limit of 100 bytes"));
+        // Parsing stopped partway through the input, at most one byte past
+        // the limit however the input happens to be buffered
+        assert!(reader.position() <= 101, "read {} bytes", reader.position());
+        assert!(from_reader_capped(std::io::Cursor::new(text), text.len() - 1).is_err());
+    }
+
+    #[mockalloc::test]
+    fn can_parse_from_slice() {
+        let x = from_slice(br#"{"a": [1, "b", null]}"#).unwrap();
+
+        assert_eq!(x, ijson!({"a": [1, "b", null]}));
+        assert!(from_slice(b"{").is_err());
+    }
+
+    #[mockalloc::test]
+    fn can_parse_ndjson() {
+        let x = from_ndjson("{\"a\": 1}\n\n[2, 3]\n\"b\"\n").unwrap();
+        assert_eq!(IValue::from(x), ijson!([{"a": 1}, [2, 3], "b"]));
+        assert!(from_ndjson("").unwrap().is_empty());
+
+        let err = from_ndjson("1\n{\"a\": }\n3").unwrap_err();
+        assert_eq!(err.line(), 2);
+    }
+
+    #[mockalloc::test]
+    fn can_limit_string_len() {
+        init_cache();
+        let limits = DeserializeLimits::new().max_string_len(3);
+        let parse = |s: &str| limits.deserialize(&mut serde_json::Deserializer::from_str(s));
+
+        assert_eq!(
+            parse(r#"{"abc": ["foo", "bar"]}"#).unwrap(),
+            ijson!({"abc": ["foo", "bar"]})
+        );
+        let err = parse(r#"["foo", "food"]"#).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("string of length 4 exceeds the maximum of 3"));
+        assert!(parse(r#"{"abcd": 1}"#).is_err());
+    }
+
+    #[cfg(feature = "direct_strings")]
+    #[mockalloc::test]
+    fn can_parse_direct_strings() {
+        let text = r#"{"key": ["value", "key"]}"#;
+        let limits = DeserializeLimits::new().direct_strings(true);
+        let x = limits
+            .deserialize(&mut serde_json::Deserializer::from_str(text))
+            .unwrap();
+
+        assert_eq!(x, ijson!({"key": ["value", "key"]}));
+        let obj = x.as_object().unwrap();
+        assert!(obj.keys().all(IString::is_interned));
+        assert!(obj["key"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|v| !v.as_string().unwrap().is_interned()));
+        assert!(limits
+            .max_string_len(3)
+            .deserialize(&mut serde_json::Deserializer::from_str(text))
+            .is_err());
+    }
+
+    #[mockalloc::test]
+    fn can_parse_top_level_type() {
+        let obj = parse_object(r#" {"a": [1]} "#).unwrap();
+        let arr = parse_array("[1, {}]").unwrap();
+
+        assert_eq!(IValue::from(obj), ijson!({"a": [1]}));
+        assert_eq!(IValue::from(arr), ijson!([1, {}]));
+        let err = parse_object("[1]").unwrap_err();
+        assert!(err.is_data());
+        assert!(err.to_string().starts_with("invalid type: sequence"));
+        assert!(parse_array(r#"{"a": 1}"#).unwrap_err().is_data());
+        assert!(parse_array("\"a\"").unwrap_err().is_data());
+        assert!(parse_object("{").unwrap_err().is_eof());
+    }
+
+    #[cfg(feature = "preserve_number_text")]
+    #[mockalloc::test]
+    fn type_errors_show_preserved_number_text() {
+        let x: IValue = serde_json::from_str("1.00").unwrap();
+        let err = from_value::<String>(&x).unwrap_err();
+        assert!(err.to_string().starts_with("invalid type: 1.00"), "{}", err);
+    }
+
+    #[mockalloc::test]
+    fn can_convert_to_vec() {
+        let x: IValue = ijson!([1, -2, 3]);
+        assert_eq!(x.to_vec::<i32>().unwrap(), vec![1, -2, 3]);
+        assert!(ijson!([1, "a"]).to_vec::<i32>().is_err());
+        assert!(ijson!({}).to_vec::<i32>().is_err());
+
+        let y: IValue = ijson!(["a", "b"]);
+        assert_eq!(y.to_vec::<&str>().unwrap(), vec!["a", "b"]);
+    }
+
+    #[mockalloc::test]
+    fn can_convert_to_map() {
+        let x: IValue = ijson!({ "a": "b", "c": "d" });
+        let map: HashMap<String, String> = x.to_map().unwrap();
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["a"], "b");
+        assert_eq!(map["c"], "d");
+        assert!(ijson!({ "a": 1 }).to_map::<String>().is_err());
+        assert!(ijson!([]).to_map::<String>().is_err());
+    }
+}
@@ -17,36 +17,76 @@
 //!   to be eagerly initialized on startup.
 //!   There is no performance benefit to this, but it can help avoid false positives
 //!   from tools like `mockalloc` which try to detect memory leaks during tests.
+//!
+//! - `arbitrary_precision`
+//!   Enables the feature of the same name in `serde_json`, and stores numbers
+//!   which cannot be represented exactly by an `i64`, `u64` or `f64` as their
+//!   original text, so that they are serialized back out unchanged.
+//!
+//! - `preserve_number_text`
+//!   Implies `arbitrary_precision`, and also stores the original text of any
+//!   number which would otherwise be serialized differently (eg. `1.00` or
+//!   `-0`), so that re-serializing a document reproduces its numbers exactly.
+//!   The text is stored alongside the number's value, so conversions and
+//!   comparisons behave as they would without this feature.
+//!   The only exception is that `serde_json` always normalizes exponents to
+//!   the form `1e+2` or `1e-2` while parsing.
+//!
+//! - `snapshot`
+//!   Enables `IValue::snapshot`, which shares arrays and objects until they
+//!   are mutated (copy-on-write). This adds a reference count to the header of
+//!   every array and object allocation, and an atomic operation whenever one is
+//!   dropped, so it is not enabled by default.
+//!
+//! - `direct_strings`
+//!   Enables `IString::direct` and `DeserializeLimits::direct_strings`, which
+//!   store strings without interning them, for documents which are parsed
+//!   once and whose strings are rarely repeated. Comparing or hashing any
+//!   `IString` then has to check whether it was interned, and hashing always
+//!   hashes the contents of the string.
 #![deny(missing_docs, missing_debug_implementations)]
 
 #[macro_use]
 mod macros;
 
 pub mod array;
+mod nodes;
 pub mod number;
 pub mod object;
+mod pointer;
+mod schema;
+mod spans;
 pub mod string;
 mod thin;
 mod value;
 
 pub use array::IArray;
-pub use number::INumber;
+pub use nodes::{NodeCounts, NodesMut};
+pub use number::{INumber, NumberClass};
 pub use object::IObject;
-pub use string::IString;
+pub use schema::ValidationError;
+pub use spans::{from_str_with_spans, SpanMap};
+pub use string::{IString, TooLong};
 pub use value::{
-    BoolMut, Destructured, DestructuredMut, DestructuredRef, IValue, ValueIndex, ValueType,
+    BoolMut, Destructured, DestructuredMut, DestructuredRef, IValue, NumberError, TypeError,
+    ValueIndex, ValueType,
 };
 
 mod de;
 mod ser;
-pub use de::from_value;
-pub use ser::to_value;
+pub use de::{
+    from_ndjson, from_reader, from_reader_capped, from_slice, from_value, parse_array,
+    parse_object, DeserializeLimits,
+};
+pub use ser::{to_string_plain_numbers, to_value, NumberFormatter};
 
 #[cfg(all(test, not(miri)))]
 mod tests {
     use mockalloc::Mockalloc;
     use std::alloc::System;
 
+    // Tests which inspect allocations with `mockalloc::record_allocs` use a
+    // plain `#[test]`, since `mockalloc::test` already records them.
     #[global_allocator]
     static ALLOCATOR: Mockalloc<System> = Mockalloc(System);
 }
@@ -0,0 +1,206 @@
+use std::marker::PhantomData;
+
+use super::value::{DestructuredMut, DestructuredRef, IValue};
+
+/// A cursor over every node of an [`IValue`] in depth-first pre-order,
+/// returned from [`IValue::nodes_mut`].
+///
+/// This cannot implement `Iterator`, as that would allow a node and one of its
+/// children to be borrowed at the same time. Instead, each node returned by
+/// [`NodesMut::next`] borrows the cursor, and the children of a node are only
+/// visited after that borrow has ended, so any changes made to the node are
+/// taken into account.
+///
+/// ```
+/// # use ijson::ijson;
+/// let mut value = ijson!([1, [2, 3]]);
+/// let mut nodes = value.nodes_mut();
+/// while let Some(node) = nodes.next() {
+///     if let Some(n) = node.to_i32() {
+///         *node = (n * 10).into();
+///     }
+/// }
+/// assert_eq!(value, ijson!([10, [20, 30]]));
+/// ```
+#[derive(Debug)]
+pub struct NodesMut<'a> {
+    // Nodes which are still to be visited, with the next one at the end.
+    stack: Vec<*mut IValue>,
+    // The node most recently returned, whose children are yet to be added
+    // to the stack.
+    current: Option<*mut IValue>,
+    phantom: PhantomData<&'a mut IValue>,
+}
+
+impl NodesMut<'_> {
+    /// Returns the next node, or `None` once every node has been visited.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<&mut IValue> {
+        if let Some(current) = self.current.take() {
+            // Safety: The node returned by the previous call is no longer
+            // borrowed, and is not reachable from any other pointer on the stack.
+            let start = self.stack.len();
+            match unsafe { &mut *current }.destructure_mut() {
+                DestructuredMut::Array(arr) => {
+                    self.stack.extend(arr.iter_mut().map(|v| v as *mut IValue));
+                }
+                DestructuredMut::Object(obj) => {
+                    self.stack
+                        .extend(obj.values_mut().map(|v| v as *mut IValue));
+                }
+                _ => {}
+            }
+            self.stack[start..].reverse();
+        }
+        let next = self.stack.pop()?;
+        self.current = Some(next);
+        // Safety: Each pointer on the stack refers to a distinct node within
+        // the borrowed value, which has not been modified since it was added.
+        Some(unsafe { &mut *next })
+    }
+
+    /// Prevents the children of the node most recently returned from being
+    /// visited.
+    pub fn skip_children(&mut self) {
+        self.current = None;
+    }
+}
+
+/// The number of each type of value within an [`IValue`], returned from
+/// [`IValue::count_nodes`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeCounts {
+    /// The number of `null`s.
+    pub nulls: usize,
+    /// The number of booleans.
+    pub bools: usize,
+    /// The number of numbers.
+    pub numbers: usize,
+    /// The number of strings, not including object keys.
+    pub strings: usize,
+    /// The number of arrays.
+    pub arrays: usize,
+    /// The number of objects.
+    pub objects: usize,
+    /// The maximum nesting depth, where the outermost value has a depth of one.
+    pub max_depth: usize,
+}
+
+impl NodeCounts {
+    /// The total number of values.
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.nulls + self.bools + self.numbers + self.strings + self.arrays + self.objects
+    }
+
+    fn visit(&mut self, value: &IValue, depth: usize) {
+        self.max_depth = self.max_depth.max(depth);
+        match value.destructure_ref() {
+            DestructuredRef::Null => self.nulls += 1,
+            DestructuredRef::Bool(_) => self.bools += 1,
+            DestructuredRef::Number(_) => self.numbers += 1,
+            DestructuredRef::String(_) => self.strings += 1,
+            DestructuredRef::Array(arr) => {
+                self.arrays += 1;
+                for v in arr {
+                    self.visit(v, depth + 1);
+                }
+            }
+            DestructuredRef::Object(obj) => {
+                self.objects += 1;
+                for v in obj.values() {
+                    self.visit(v, depth + 1);
+                }
+            }
+        }
+    }
+}
+
+impl IValue {
+    /// Counts the values of each type within this value, including itself.
+    #[must_use]
+    pub fn count_nodes(&self) -> NodeCounts {
+        let mut res = NodeCounts::default();
+        res.visit(self, 1);
+        res
+    }
+
+    /// Returns a cursor over mutable references to this value and every value
+    /// nested within it, in depth-first pre-order. See [`NodesMut`].
+    pub fn nodes_mut(&mut self) -> NodesMut<'_> {
+        NodesMut {
+            stack: vec![self as *mut IValue],
+            current: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[mockalloc::test]
+    fn can_visit_in_pre_order() {
+        let mut x: IValue = ijson!([1, {"a": [2, 3], "b": 4}, 5]);
+        let mut visited = Vec::new();
+        let mut nodes = x.nodes_mut();
+        while let Some(node) = nodes.next() {
+            visited.push(node.clone());
+        }
+
+        assert_eq!(visited.len(), 8);
+        assert_eq!(visited[0], x);
+        let scalars: Vec<_> = visited.iter().filter_map(IValue::to_i32).collect();
+        assert_eq!(scalars, [1, 2, 3, 4, 5]);
+    }
+
+    #[mockalloc::test]
+    fn can_stop_after_first_match() {
+        let mut x: IValue = ijson!({"a": [1, 20, 30], "b": 40});
+        let mut nodes = x.nodes_mut();
+        while let Some(node) = nodes.next() {
+            if node.to_i32().filter(|&n| n > 10).is_some() {
+                *node = ijson!(["replaced"]);
+                break;
+            }
+        }
+
+        assert_eq!(x, ijson!({"a": [1, ["replaced"], 30], "b": 40}));
+    }
+
+    #[mockalloc::test]
+    fn visits_children_after_mutation() {
+        let mut x: IValue = ijson!([[1]]);
+        let mut nodes = x.nodes_mut();
+        nodes.next();
+        let inner = nodes.next().unwrap();
+        inner.as_array_mut().unwrap().push(2);
+        let mut rest = Vec::new();
+        while let Some(node) = nodes.next() {
+            rest.push(node.clone());
+        }
+
+        assert_eq!(rest, [IValue::from(1), IValue::from(2)]);
+    }
+
+    #[mockalloc::test]
+    fn can_count_nodes() {
+        let x: IValue = ijson!({"a": [1, "b", null, {"c": [true, 2.5]}], "d": {}, "e": "f"});
+
+        assert_eq!(
+            x.count_nodes(),
+            NodeCounts {
+                nulls: 1,
+                bools: 1,
+                numbers: 2,
+                strings: 2,
+                arrays: 2,
+                objects: 3,
+                max_depth: 5,
+            }
+        );
+        assert_eq!(x.count_nodes().total(), 11);
+        assert_eq!(IValue::NULL.count_nodes().max_depth, 1);
+    }
+}
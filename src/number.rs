@@ -19,16 +19,60 @@ enum NumberType {
     I64,
     U64,
     F64,
+    // The exact text of a number which none of the other types can represent
+    #[cfg(feature = "arbitrary_precision")]
+    Big,
 }
 
 #[repr(C)]
 #[repr(align(4))]
 struct Header {
     type_: NumberType,
+    // Low bits of an `I24`, or `HAS_TEXT` for the other heap types
     short: u8,
     static_: i16,
 }
 
+// Marks an `I64`, `U64` or `F64` whose value is followed by the original text
+// of the number, which is only used when serializing it.
+#[cfg(feature = "preserve_number_text")]
+const HAS_TEXT: u8 = 1;
+
+// The key `serde_json` uses to pass around the text of a number when its
+// `arbitrary_precision` feature is enabled.
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+// Splits the text of a JSON number into its sign, significant digits and
+// exponent, such that two numbers have the same parts exactly when they have
+// the same value (apart from the sign of zero).
+#[cfg(feature = "arbitrary_precision")]
+fn decimal_parts(text: &str) -> Option<(bool, String, i64)> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let (mantissa, exponent) = match text.find(&['e', 'E'][..]) {
+        Some(i) => (&text[..i], text[i + 1..].parse::<i64>().ok()?),
+        None => (text, 0),
+    };
+    let (int, frac) = match mantissa.find('.') {
+        Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+        None => (mantissa, ""),
+    };
+    let digits = format!("{}{}", int, frac);
+    let trimmed = digits.trim_end_matches('0');
+    let exponent = exponent
+        .checked_sub(i64::try_from(frac.len()).ok()?)?
+        .checked_add(i64::try_from(digits.len() - trimmed.len()).ok()?)?;
+    let trimmed = trimmed.trim_start_matches('0');
+    if trimmed.is_empty() {
+        Some((negative, String::new(), 0))
+    } else {
+        Some((negative, trimmed.to_owned(), exponent))
+    }
+}
+
 fn can_represent_as_f64(x: u64) -> bool {
     x.leading_zeros() + x.trailing_zeros() >= 11
 }
@@ -80,6 +124,29 @@ trait HeaderRef<'a>: ThinRefExt<'a, Header> {
     unsafe fn f64_unchecked(&self) -> &'a f64 {
         &*self.payload_ptr().cast()
     }
+    // Safety: `len_ptr` must point to a length followed by that much text
+    #[cfg(feature = "arbitrary_precision")]
+    unsafe fn text_at(len_ptr: *const u64) -> &'a str {
+        let bytes = std::slice::from_raw_parts(len_ptr.add(1).cast::<u8>(), *len_ptr as usize);
+        std::str::from_utf8_unchecked(bytes)
+    }
+    #[cfg(feature = "arbitrary_precision")]
+    unsafe fn big_unchecked(&self) -> &'a str {
+        Self::text_at(self.payload_ptr())
+    }
+    #[cfg(feature = "preserve_number_text")]
+    fn has_text(&self) -> bool {
+        matches!(
+            self.type_,
+            NumberType::I64 | NumberType::U64 | NumberType::F64
+        ) && self.short == HAS_TEXT
+    }
+    // Safety: the number must have text, see `has_text`
+    #[cfg(feature = "preserve_number_text")]
+    unsafe fn text_unchecked(&self) -> &'a str {
+        // The text follows the value
+        Self::text_at(self.payload_ptr().add(1))
+    }
     fn to_i64(&self) -> Option<i64> {
         // Safety: We only call methods appropriate for the type
         unsafe {
@@ -99,6 +166,8 @@ trait HeaderRef<'a>: ThinRefExt<'a, Header> {
                         None
                     }
                 }
+                #[cfg(feature = "arbitrary_precision")]
+                NumberType::Big => None,
             }
         }
     }
@@ -138,6 +207,8 @@ trait HeaderRef<'a>: ThinRefExt<'a, Header> {
                         None
                     }
                 }
+                #[cfg(feature = "arbitrary_precision")]
+                NumberType::Big => None,
             }
         }
     }
@@ -169,6 +240,8 @@ trait HeaderRef<'a>: ThinRefExt<'a, Header> {
                     }
                 }
                 NumberType::F64 => Some(*self.f64_unchecked()),
+                #[cfg(feature = "arbitrary_precision")]
+                NumberType::Big => None,
             }
         }
     }
@@ -208,6 +281,8 @@ trait HeaderRef<'a>: ThinRefExt<'a, Header> {
                         None
                     }
                 }
+                #[cfg(feature = "arbitrary_precision")]
+                NumberType::Big => None,
             }
         }
     }
@@ -215,6 +290,8 @@ trait HeaderRef<'a>: ThinRefExt<'a, Header> {
         match self.type_ {
             NumberType::Static | NumberType::I24 | NumberType::I64 | NumberType::U64 => false,
             NumberType::F64 => true,
+            #[cfg(feature = "arbitrary_precision")]
+            NumberType::Big => unsafe { self.big_unchecked() }.contains(&['.', 'e', 'E'][..]),
         }
     }
     fn to_f64_lossy(&self) -> f64 {
@@ -225,6 +302,9 @@ trait HeaderRef<'a>: ThinRefExt<'a, Header> {
                 NumberType::I64 => *self.i64_unchecked() as f64,
                 NumberType::U64 => *self.u64_unchecked() as f64,
                 NumberType::F64 => *self.f64_unchecked(),
+                // Overflowing numbers parse as infinity
+                #[cfg(feature = "arbitrary_precision")]
+                NumberType::Big => self.big_unchecked().parse().unwrap(),
             }
         }
     }
@@ -242,9 +322,16 @@ trait HeaderRef<'a>: ThinRefExt<'a, Header> {
                         .f64_unchecked()
                         .partial_cmp(other.f64_unchecked())
                         .unwrap(),
+                    #[cfg(feature = "arbitrary_precision")]
+                    NumberType::Big => self.cmp_big(other),
                 }
             }
         } else {
+            #[cfg(feature = "arbitrary_precision")]
+            if self.type_ == NumberType::Big || other.type_ == NumberType::Big {
+                return self.cmp_big(other);
+            }
+
             // Safety: We only call methods for the appropriate type
             unsafe {
                 match (self.type_, other.type_) {
@@ -278,6 +365,22 @@ trait HeaderRef<'a>: ThinRefExt<'a, Header> {
             }
         }
     }
+    // Big numbers are only stored when no other type can represent them, so
+    // they are never equal to a number of another type. Ties between the
+    // approximate values are broken by type, and then by the text itself.
+    #[cfg(feature = "arbitrary_precision")]
+    fn cmp_big<'b>(&self, other: impl HeaderRef<'b>) -> Ordering {
+        let self_is_big = self.type_ == NumberType::Big;
+        let other_is_big = other.type_ == NumberType::Big;
+        self.to_f64_lossy()
+            .partial_cmp(&other.to_f64_lossy())
+            .unwrap()
+            .then(self_is_big.cmp(&other_is_big))
+            .then_with(|| {
+                // Safety: Both numbers must be big if they compared equal so far
+                unsafe { self.big_unchecked().cmp(other.big_unchecked()) }
+            })
+    }
 }
 
 trait HeaderMut<'a>: ThinMutExt<'a, Header> {
@@ -326,11 +429,29 @@ static STATIC_NUMBERS: [Header; STATIC_LEN] =
 const SHORT_LOWER: i64 = -0x0080_0000;
 const SHORT_UPPER: i64 = 0x0080_0000;
 
+/// How a number is represented, returned from [`INumber::classify`].
+///
+/// Unlike the internal representation of an `INumber`, this does not depend on
+/// the magnitude of the number beyond which primitive type is needed to store it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NumberClass {
+    /// An integer which fits in an `i64`.
+    Signed,
+    /// An integer which is too large for an `i64`, but fits in a `u64`.
+    Unsigned,
+    /// A floating point number, which fits in an `f64`.
+    Float,
+    /// A number stored as its original text, which is only produced when the
+    /// `arbitrary_precision` feature is enabled.
+    Arbitrary,
+}
+
 /// The `INumber` type represents a JSON number. It is decoupled from any specific
-/// representation, and internally uses several. There is no way to determine the
-/// internal representation: instead the caller is expected to convert the number
-/// using one of the fallible `to_xxx` functions and handle the cases where the
-/// number does not convert to the desired type.
+/// representation, and internally uses several. Other than the coarse
+/// [`NumberClass`], there is no way to determine the internal representation:
+/// instead the caller is expected to convert the number using one of the fallible
+/// `to_xxx` functions and handle the cases where the number does not convert to
+/// the desired type.
 ///
 /// Special floating point values (eg. NaN, Infinity, etc.) cannot be stored within
 /// an `INumber`.
@@ -341,9 +462,12 @@ const SHORT_UPPER: i64 = 0x0080_0000;
 /// `2.0` will succeed with the value `2`.
 ///
 /// Currently `INumber` can store any number representable with an `f64`, `i64` or
-/// `u64`. It is expected that in the future it will be further expanded to store
-/// integers and possibly decimals to arbitrary precision, but that is not currently
-/// the case.
+/// `u64`. When the `arbitrary_precision` feature is enabled, numbers deserialized
+/// from `serde_json` which cannot be represented exactly this way are instead
+/// stored as their original text, and can only be converted lossily. With the
+/// `preserve_number_text` feature, any number whose text would otherwise change
+/// when serialized, such as `1.00` or `1e+2`, also keeps its original text
+/// alongside its value, but the text is only used when serializing it.
 ///
 /// Any number representable with an `i8` or a `u8` can be stored in an `INumber`
 /// without a heap allocation (so JSON byte arrays are relatively efficient).
@@ -380,10 +504,33 @@ impl INumber {
                     .0
                     .pad_to_align()
             }
+            #[cfg(feature = "arbitrary_precision")]
+            NumberType::Big => unreachable!(),
         }
         Ok(res)
     }
 
+    #[cfg(feature = "arbitrary_precision")]
+    fn big_layout(len: usize) -> Result<Layout, LayoutError> {
+        Ok(Layout::new::<Header>()
+            .extend(Layout::new::<u64>().align_to(8)?)?
+            .0
+            .extend(Layout::array::<u8>(len)?)?
+            .0
+            .pad_to_align())
+    }
+
+    // The layout of an `I64`, `U64` or `F64` followed by `len` bytes of text.
+    #[cfg(feature = "preserve_number_text")]
+    fn text_layout(len: usize) -> Result<Layout, LayoutError> {
+        Ok(Layout::new::<Header>()
+            .extend(Layout::new::<[u64; 2]>().align_to(8)?)?
+            .0
+            .extend(Layout::array::<u8>(len)?)?
+            .0
+            .pad_to_align())
+    }
+
     fn alloc(type_: NumberType) -> *mut Header {
         unsafe {
             let ptr = alloc(Self::layout(type_).unwrap()).cast::<Header>();
@@ -398,7 +545,19 @@ impl INumber {
 
     fn dealloc(ptr: *mut Header) {
         unsafe {
-            let layout = Self::layout((*ptr).type_).unwrap();
+            let hd = ThinRef::new(ptr);
+            #[cfg(feature = "preserve_number_text")]
+            if hd.has_text() {
+                let layout = Self::text_layout(hd.text_unchecked().len()).unwrap();
+                dealloc(ptr.cast::<u8>(), layout);
+                return;
+            }
+            let layout = match hd.type_ {
+                #[cfg(feature = "arbitrary_precision")]
+                NumberType::Big => Self::big_layout(hd.big_unchecked().len()),
+                type_ => Self::layout(type_),
+            }
+            .unwrap();
             dealloc(ptr.cast::<u8>(), layout);
         }
     }
@@ -430,11 +589,11 @@ impl INumber {
             ))
         }
     }
-    fn header(&self) -> ThinRef<Header> {
+    fn header(&self) -> ThinRef<'_, Header> {
         unsafe { ThinRef::new(self.0.ptr().cast()) }
     }
 
-    fn header_mut(&mut self) -> ThinMut<Header> {
+    fn header_mut(&mut self) -> ThinMut<'_, Header> {
         unsafe { ThinMut::new(self.0.ptr().cast()) }
     }
 
@@ -493,8 +652,151 @@ impl INumber {
         res
     }
 
+    #[cfg(feature = "arbitrary_precision")]
+    fn new_big(text: &str) -> Self {
+        unsafe {
+            let ptr = alloc(Self::big_layout(text.len()).unwrap()).cast::<Header>();
+            ptr.write(Header {
+                type_: NumberType::Big,
+                static_: 0,
+                short: 0,
+            });
+            let payload_ptr = ThinMut::new(ptr).payload_ptr_mut();
+            payload_ptr.write(text.len() as u64);
+            std::ptr::copy_nonoverlapping(
+                text.as_ptr(),
+                payload_ptr.add(1).cast::<u8>(),
+                text.len(),
+            );
+            INumber(IValue::new_ptr(ptr.cast::<u8>(), TypeTag::Number))
+        }
+    }
+
+    // Copies the value of a number which is not big, followed by `text`.
+    #[cfg(feature = "preserve_number_text")]
+    fn with_text(number: &INumber, text: &str) -> Self {
+        let hd = number.header();
+        // Safety: We only call methods appropriate for the matched type
+        let (type_, bits) = unsafe {
+            match hd.type_ {
+                NumberType::U64 => (NumberType::U64, *hd.u64_unchecked()),
+                NumberType::F64 => (NumberType::F64, hd.f64_unchecked().to_bits()),
+                // Smaller integers are widened, as the header holds the flag
+                _ => (NumberType::I64, hd.to_i64().unwrap() as u64),
+            }
+        };
+        unsafe {
+            let ptr = alloc(Self::text_layout(text.len()).unwrap()).cast::<Header>();
+            ptr.write(Header {
+                type_,
+                static_: 0,
+                short: HAS_TEXT,
+            });
+            let payload_ptr = ThinMut::new(ptr).payload_ptr_mut();
+            payload_ptr.write(bits);
+            payload_ptr.add(1).write(text.len() as u64);
+            std::ptr::copy_nonoverlapping(
+                text.as_ptr(),
+                payload_ptr.add(2).cast::<u8>(),
+                text.len(),
+            );
+            INumber(IValue::new_ptr(ptr.cast::<u8>(), TypeTag::Number))
+        }
+    }
+
+    /// Parses the text of a JSON number, as provided by `serde_json` when its
+    /// `arbitrary_precision` feature is enabled.
+    ///
+    /// Numbers which can be stored exactly as an `i64`, `u64` or `f64` are
+    /// stored that way, so they behave exactly as they would without this
+    /// feature. Otherwise the text itself is stored, and will be serialized
+    /// back out unchanged. Such numbers can only be converted to other numeric
+    /// types lossily.
+    ///
+    /// With the `preserve_number_text` feature, the text is also stored
+    /// alongside the value if the number would otherwise be serialized
+    /// differently, eg. `1.00` or `1e+2`. Only serialization uses this text.
+    #[cfg(feature = "arbitrary_precision")]
+    pub(crate) fn from_json_text(text: &str) -> Option<Self> {
+        let compact = Self::compact_from_json_text(text)?;
+        #[cfg(feature = "preserve_number_text")]
+        let compact = compact.map(|n| {
+            if serde_json::to_string(&n).ok().as_deref() == Some(text) {
+                n
+            } else {
+                Self::with_text(&n, text)
+            }
+        });
+        Some(compact.unwrap_or_else(|| Self::new_big(text)))
+    }
+
+    // Returns `Some(None)` if the number is valid, but cannot be stored exactly
+    // without its text.
+    #[cfg(feature = "arbitrary_precision")]
+    fn compact_from_json_text(text: &str) -> Option<Option<Self>> {
+        let number: serde_json::Number = text.parse().ok()?;
+        if let Some(v) = number.as_i64() {
+            return Some(Some(v.into()));
+        } else if let Some(v) = number.as_u64() {
+            return Some(Some(v.into()));
+        }
+        // The nearest `f64` is exact if it is written out with the same value
+        // as the original text.
+        if let Some(v) = number.as_f64() {
+            let written = serde_json::to_string(&v).ok()?;
+            let parts = decimal_parts(text);
+            if parts.is_some() && parts == decimal_parts(&written) {
+                return Some(INumber::try_from(v).ok());
+            }
+        }
+        Some(None)
+    }
+
+    /// Returns the original text of this number, if it was stored as text.
+    ///
+    /// This is only the case for numbers deserialized with the
+    /// `arbitrary_precision` feature enabled which could not otherwise be
+    /// serialized back out unchanged. Numbers which cannot be stored exactly
+    /// any other way are compared by their text when their values are
+    /// approximately equal. With the `preserve_number_text` feature, numbers
+    /// such as `1.00` keep their exact value as well, which is used for all
+    /// conversions and comparisons, so `1.00` and `1.0` are equal.
+    #[must_use]
+    pub fn source_text(&self) -> Option<&str> {
+        #[cfg(feature = "preserve_number_text")]
+        {
+            let hd = self.header();
+            if hd.has_text() {
+                // Safety: We checked the number has text
+                return Some(unsafe { hd.text_unchecked() });
+            }
+        }
+        #[cfg(feature = "arbitrary_precision")]
+        return self.big_text();
+        #[cfg(not(feature = "arbitrary_precision"))]
+        None
+    }
+
+    // Returns the original text of the number if it cannot be stored any
+    // other way.
+    #[cfg(feature = "arbitrary_precision")]
+    pub(crate) fn big_text(&self) -> Option<&str> {
+        let hd = self.header();
+        if hd.type_ == NumberType::Big {
+            // Safety: We checked the type
+            Some(unsafe { hd.big_unchecked() })
+        } else {
+            None
+        }
+    }
+
     pub(crate) fn clone_impl(&self) -> IValue {
         let hd = self.header();
+        #[cfg(feature = "preserve_number_text")]
+        if hd.has_text() {
+            // Safety: We checked the number has text
+            return Self::with_text(self, unsafe { hd.text_unchecked() }).0;
+        }
         // Safety: We only call methods appropriate for the matched type
         unsafe {
             match hd.type_ {
@@ -503,6 +805,8 @@ impl INumber {
                 NumberType::I64 => Self::new_i64(*hd.i64_unchecked()).0,
                 NumberType::U64 => Self::new_u64(*hd.u64_unchecked()).0,
                 NumberType::F64 => Self::new_f64(*hd.f64_unchecked()).0,
+                #[cfg(feature = "arbitrary_precision")]
+                NumberType::Big => Self::new_big(hd.big_unchecked()).0,
             }
         }
     }
@@ -572,11 +876,56 @@ impl INumber {
     pub fn has_decimal_point(&self) -> bool {
         self.header().has_decimal_point()
     }
+
+    /// Returns which primitive type this number is stored as.
+    ///
+    /// Integers are always stored as [`NumberClass::Signed`] when they fit in
+    /// an `i64`, regardless of the type they were created from.
+    #[must_use]
+    pub fn classify(&self) -> NumberClass {
+        match self.header().type_ {
+            NumberType::Static | NumberType::I24 | NumberType::I64 => NumberClass::Signed,
+            NumberType::U64 => NumberClass::Unsigned,
+            NumberType::F64 => NumberClass::Float,
+            #[cfg(feature = "arbitrary_precision")]
+            NumberType::Big => NumberClass::Arbitrary,
+        }
+    }
+
+    // Integer sums are exact wherever the result fits in an `i64` or `u64`,
+    // everything else is summed as `f64`, saturating at the largest finite
+    // `f64` of the same sign.
+    pub(crate) fn saturating_add(&self, other: &INumber) -> INumber {
+        if !self.has_decimal_point() && !other.has_decimal_point() {
+            let as_i128 = |n: &INumber| {
+                n.to_i64()
+                    .map(i128::from)
+                    .or_else(|| n.to_u64().map(i128::from))
+            };
+            if let (Some(a), Some(b)) = (as_i128(self), as_i128(other)) {
+                let sum = a + b;
+                if let Ok(v) = i64::try_from(sum) {
+                    return v.into();
+                } else if let Ok(v) = u64::try_from(sum) {
+                    return v.into();
+                }
+            }
+        }
+        let sum = (self.to_f64_lossy() + other.to_f64_lossy()).clamp(f64::MIN, f64::MAX);
+        // The sum can only be NaN if both sides were stored as text too large
+        // for an `f64`, with opposite signs.
+        INumber::try_from(sum).unwrap_or_else(|_| self.clone())
+    }
 }
 
 impl Hash for INumber {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         let hd = self.header();
+        #[cfg(feature = "arbitrary_precision")]
+        if let Some(text) = self.big_text() {
+            text.hash(state);
+            return;
+        }
         if let Some(v) = hd.to_i64() {
             v.hash(state);
         } else if let Some(v) = hd.to_u64() {
@@ -692,6 +1041,10 @@ impl PartialOrd for INumber {
 
 impl Debug for INumber {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "arbitrary_precision")]
+        if let Some(text) = self.big_text() {
+            return f.write_str(text);
+        }
         if let Some(v) = self.to_i64() {
             Debug::fmt(&v, f)
         } else if let Some(v) = self.to_u64() {
@@ -795,4 +1148,128 @@ mod tests {
         assert!(INumber::try_from(-1e30).unwrap() < INumber::from(i64::MIN));
         assert!(INumber::try_from(99_999_999_000.0).unwrap() < INumber::from(99_999_999_001_u64));
     }
+
+    #[cfg(feature = "arbitrary_precision")]
+    #[mockalloc::test]
+    fn can_round_trip_arbitrary_precision() {
+        let text = "1234567890123456789012345678901234567890";
+        let x: IValue = serde_json::from_str(text).unwrap();
+        assert_eq!(serde_json::to_string(&x).unwrap(), text);
+        assert_eq!(x.to_u64(), None);
+        assert_eq!(x.to_f64_lossy(), Some(1.2345678901234568e39));
+        assert!(x > IValue::from(u64::MAX));
+        assert_eq!(x.clone(), x);
+
+        let text = r#"[1,-2.5,3.14159265358979323846264338327950288]"#;
+        let x: IValue = serde_json::from_str(text).unwrap();
+        assert_eq!(x[0], IValue::from(1));
+        assert_eq!(x[1], IValue::from(-2.5));
+        assert_eq!(serde_json::to_string(&x).unwrap(), text);
+        let v: serde_json::Value = crate::from_value(&x).unwrap();
+        assert_eq!(v.to_string(), text);
+
+        let y: IValue =
+            crate::to_value(serde_json::from_str::<serde_json::Value>(text).unwrap()).unwrap();
+        assert_eq!(x, y);
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    #[mockalloc::test]
+    fn stores_exact_numbers_compactly() {
+        let parse = |text: &str| -> IValue { serde_json::from_str(text).unwrap() };
+
+        let x = parse("0.30000000000000004");
+        assert_eq!(x.to_f64(), Some(0.30000000000000004));
+        assert_eq!(x, IValue::from(0.30000000000000004));
+        assert_eq!(x.as_number().unwrap().source_text(), None);
+
+        let x = parse("1.7976931348623157e308");
+        assert_eq!(x.to_f64(), Some(f64::MAX));
+        assert_eq!(x, IValue::from(f64::MAX));
+        assert_eq!(x.as_number().unwrap().source_text(), None);
+
+        let x = parse("100000000000000000000");
+        assert_eq!(x.to_f64(), Some(1e20));
+        assert_eq!(x, IValue::from(1e20));
+        assert!(x > IValue::from(u64::MAX));
+        // Serialized as `1e20`, so the text is kept with `preserve_number_text`
+        #[cfg(not(feature = "preserve_number_text"))]
+        assert_eq!(x.as_number().unwrap().source_text(), None);
+
+        // One more digit than an `f64` can hold
+        let x = parse("0.300000000000000041");
+        assert_eq!(
+            x.as_number().unwrap().source_text(),
+            Some("0.300000000000000041")
+        );
+        assert_eq!(x.to_f64(), None);
+    }
+
+    #[cfg(feature = "preserve_number_text")]
+    #[mockalloc::test]
+    fn can_preserve_number_text() {
+        let text = "[1.00,1e+2,1.0,100,-0,1.5e-3,2.5]";
+        let x: IValue = serde_json::from_str(text).unwrap();
+        assert_eq!(serde_json::to_string(&x).unwrap(), text);
+        // `serde_json` itself normalizes exponents
+        let y: IValue = serde_json::from_str("[1e2,1E-2]").unwrap();
+        assert_eq!(serde_json::to_string(&y).unwrap(), "[1e+2,1e-2]");
+
+        let source_text = |v: &IValue| v.as_number().unwrap().source_text().map(String::from);
+        assert_eq!(source_text(&x[0]).as_deref(), Some("1.00"));
+        assert_eq!(source_text(&x[1]).as_deref(), Some("1e+2"));
+        assert_eq!(source_text(&x[2]), None);
+        assert_eq!(source_text(&x[3]), None);
+        assert_eq!(source_text(&x[4]).as_deref(), Some("-0"));
+        assert_eq!(x[6], IValue::from(2.5));
+
+        // Numbers keep their values, and are equal to the same number without
+        // its text
+        assert_eq!(x[0], IValue::from(1.0));
+        assert_eq!(x[0].to_f64(), Some(1.0));
+        assert_eq!(x[0].to_i64(), Some(1));
+        assert_eq!(x[1], IValue::from(100));
+        assert_eq!(x[1].to_u64(), Some(100));
+        assert_eq!(x[4], IValue::from(0));
+        assert_eq!(x[4].to_i64(), Some(0));
+        assert!(!x[4].as_number().unwrap().has_decimal_point());
+        assert_eq!(x[5].to_f64(), Some(1.5e-3));
+        let y: IValue = serde_json::from_str("[1.000,1.5e0,1E2,100000000000000000000]").unwrap();
+        assert_eq!(y[0], x[0]);
+        assert_eq!(y[1].to_f64(), Some(1.5));
+        assert_eq!(y[2], x[1]);
+        assert_eq!(y[3].to_f64(), Some(1e20));
+        assert_eq!(
+            serde_json::to_string(&y).unwrap(),
+            "[1.000,1.5e+0,1e+2,100000000000000000000]"
+        );
+
+        let hash = |v: &IValue| {
+            let mut h = std::collections::hash_map::DefaultHasher::new();
+            v.hash(&mut h);
+            std::hash::Hasher::finish(&h)
+        };
+        assert_eq!(hash(&x[0]), hash(&IValue::from(1.0)));
+        assert_eq!(hash(&x[4]), hash(&IValue::from(0)));
+        let clone = x.clone();
+        assert_eq!(serde_json::to_string(&clone).unwrap(), text);
+        assert_eq!(clone, x);
+    }
+
+    #[mockalloc::test]
+    fn can_classify() {
+        assert_eq!(INumber::from(1).classify(), NumberClass::Signed);
+        assert_eq!(INumber::from(-1_000_000).classify(), NumberClass::Signed);
+        assert_eq!(INumber::from(i64::MIN).classify(), NumberClass::Signed);
+        assert_eq!(INumber::from(42u64).classify(), NumberClass::Signed);
+        assert_eq!(INumber::from(u64::MAX).classify(), NumberClass::Unsigned);
+        assert_eq!(
+            INumber::try_from(1.0).unwrap().classify(),
+            NumberClass::Float
+        );
+        assert_eq!(
+            INumber::try_from(0.5).unwrap().classify(),
+            NumberClass::Float
+        );
+    }
 }
@@ -1,6 +1,9 @@
 //! Functionality relating to the JSON object type
 
 use std::alloc::{alloc, dealloc, Layout, LayoutError};
+use std::borrow::Cow;
+#[cfg(test)]
+use std::cell::Cell;
 use std::cmp::{self, Ordering};
 use std::collections::hash_map::DefaultHasher;
 use std::collections::{BTreeMap, HashMap};
@@ -9,18 +12,24 @@ use std::hash::{Hash, Hasher};
 use std::iter::FromIterator;
 use std::mem;
 use std::ops::{Index, IndexMut};
+#[cfg(feature = "snapshot")]
+use std::sync::atomic::{fence, AtomicUsize, Ordering as AtomicOrdering};
 
 #[cfg(feature = "indexmap")]
 use indexmap::IndexMap;
 
 use crate::thin::{ThinMut, ThinMutExt, ThinRef, ThinRefExt};
 
+use super::array::IArray;
 use super::string::IString;
 use super::value::{IValue, TypeTag};
 
 #[repr(C)]
 #[repr(align(4))]
 struct Header {
+    // Number of `IObject`s sharing this allocation, see `IObject::snapshot`.
+    #[cfg(feature = "snapshot")]
+    rc: AtomicUsize,
     len: usize,
     cap: usize,
 }
@@ -36,7 +45,37 @@ fn hash_capacity(cap: usize) -> usize {
     cap + cap / 4
 }
 
+#[cfg(test)]
+type HashFn = fn(&IString) -> usize;
+
+#[cfg(test)]
+thread_local! {
+    // Replaces `hash_fn` on the current thread, see `with_hash_fn`.
+    static HASH_FN_OVERRIDE: Cell<Option<HashFn>> = Cell::new(None);
+}
+
+// Runs `body` with `hash_fn` replaced by `f` on the current thread, so that
+// tests can place keys in known buckets. Objects created inside `body` must
+// not be used outside of it, as their keys would then hash differently.
+#[cfg(test)]
+fn with_hash_fn<R>(f: HashFn, body: impl FnOnce() -> R) -> R {
+    struct Reset;
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            HASH_FN_OVERRIDE.with(|h| h.set(None));
+        }
+    }
+
+    HASH_FN_OVERRIDE.with(|h| h.set(Some(f)));
+    let _reset = Reset;
+    body()
+}
+
 fn hash_fn(s: &IString) -> usize {
+    #[cfg(test)]
+    if let Some(f) = HASH_FN_OVERRIDE.with(Cell::get) {
+        return f(s);
+    }
     let v: &IValue = s.as_ref();
     // We know the bottom two bits are always the same
     let mut p = v.ptr_usize() >> 2;
@@ -57,6 +96,16 @@ struct SplitHeader<'a> {
 
 impl SplitHeader<'_> {
     fn find_bucket(&self, key: &IString) -> Result<usize, usize> {
+        // Keys are always interned, so a direct string can only be present if
+        // its contents are in the string cache. The returned bucket is not
+        // meaningful, as a direct string is never inserted.
+        #[cfg(feature = "direct_strings")]
+        if !key.is_interned() {
+            return match IString::lookup(key) {
+                Some(key) => self.find_bucket(&key),
+                None => Err(usize::MAX),
+            };
+        }
         let hash_cap = hash_capacity(self.cap);
         let initial_bucket = hash_bucket(key, hash_cap);
         unsafe {
@@ -86,6 +135,30 @@ impl SplitHeader<'_> {
         }
         Err(usize::MAX)
     }
+    // Finds the bucket to insert a key which is known not to be present,
+    // skipping the key comparisons done by `find_bucket`.
+    fn find_vacant_bucket(&self, key: &IString) -> usize {
+        let hash_cap = hash_capacity(self.cap);
+        let initial_bucket = hash_bucket(key, hash_cap);
+        unsafe {
+            for i in 0..hash_cap {
+                let bucket = (initial_bucket + i) % hash_cap;
+                let index = *self.table.get_unchecked(bucket);
+
+                // Either the bucket is empty, or we evict a key with a shorter
+                // probe length, exactly as if `find_bucket` had failed here.
+                if index == usize::MAX {
+                    return bucket;
+                }
+                let k = &self.items.get_unchecked(index).key;
+                let key_dist = (bucket + hash_cap - hash_bucket(k, hash_cap)) % hash_cap;
+                if key_dist < i {
+                    return bucket;
+                }
+            }
+        }
+        usize::MAX
+    }
     // Safety: index must be in bounds
     unsafe fn find_bucket_from_index(&self, index: usize) -> usize {
         let hash_cap = hash_capacity(self.cap);
@@ -109,7 +182,7 @@ struct SplitHeaderMut<'a> {
 }
 
 impl SplitHeaderMut<'_> {
-    fn as_ref(&self) -> SplitHeader {
+    fn as_ref(&self) -> SplitHeader<'_> {
         SplitHeader {
             cap: self.cap,
             items: self.items,
@@ -267,6 +340,7 @@ impl<'a, T: ThinRefExt<'a, Header>> HeaderRef<'a> for T {}
 impl<'a> HeaderMut<'a> for ThinMut<'a, Header> {
     // Safety: Must ensure there's capacity for an extra element
     unsafe fn entry(self, key: IString) -> Entry<'a> {
+        let key = key.into_interned();
         match self.split().find_bucket(&key) {
             Err(bucket) => Entry::Vacant(VacantEntry {
                 header: self,
@@ -281,6 +355,9 @@ impl<'a> HeaderMut<'a> for ThinMut<'a, Header> {
     }
     // Safety: Must ensure there's capacity for an extra element
     unsafe fn entry_or_clone(self, key: &IString) -> Entry<'a> {
+        if !key.is_interned() {
+            return self.entry(key.clone());
+        }
         match self.split().find_bucket(key) {
             Err(bucket) => Entry::Vacant(VacantEntry {
                 header: self,
@@ -454,6 +531,41 @@ impl<'a> Entry<'a> {
         }
     }
 
+    /// Fills this entry by calling the specified function with its key if
+    /// it's currently vacant, and then returns a mutable reference to the
+    /// value at this entry.
+    pub fn or_insert_with_key(self, default: impl FnOnce(&IString) -> IValue) -> &'a mut IValue {
+        match self {
+            Entry::Occupied(occ) => occ.into_mut(),
+            Entry::Vacant(vac) => {
+                let value = default(vac.key());
+                vac.insert(value)
+            }
+        }
+    }
+
+    /// Fills this entry with `null` if it's currently vacant, and then
+    /// returns a mutable reference to the value at this entry.
+    pub fn or_default(self) -> &'a mut IValue {
+        self.or_insert(IValue::NULL)
+    }
+
+    /// Fills this entry with an empty array if it's currently vacant, and
+    /// then returns a mutable reference to the value at this entry.
+    ///
+    /// An occupied entry is returned as-is, even if it's not an array.
+    pub fn or_insert_array(self) -> &'a mut IValue {
+        self.or_insert_with(|| IArray::new().into())
+    }
+
+    /// Fills this entry with an empty object if it's currently vacant, and
+    /// then returns a mutable reference to the value at this entry.
+    ///
+    /// An occupied entry is returned as-is, even if it's not an object.
+    pub fn or_insert_object(self) -> &'a mut IValue {
+        self.or_insert_with(|| IObject::new().into())
+    }
+
     /// Returns a reference to the key at this entry.
     #[must_use]
     pub fn key(&self) -> &IString {
@@ -487,6 +599,37 @@ impl Debug for IntoIter {
     }
 }
 
+impl IntoIter {
+    /// Converts the entries which have not been yielded yet back into an
+    /// object, in their original order, reusing the allocation.
+    #[must_use]
+    pub fn into_object(self) -> IObject {
+        let mut obj = self.reversed_object;
+        if !obj.is_static() {
+            // Safety: not static, and every index added to the table is in
+            // range and not already present.
+            unsafe {
+                let mut hd = obj.header_mut();
+                let split = hd.reborrow().split_mut();
+                split.items.reverse();
+                // The table was not updated when the items were reversed, so
+                // rebuild it from scratch.
+                for item in split.table {
+                    *item = usize::MAX;
+                }
+                for index in 0..hd.len {
+                    let mut split = hd.reborrow().split_mut();
+                    let bucket = split
+                        .as_ref()
+                        .find_vacant_bucket(&split.items.get_unchecked(index).key);
+                    split.shift(bucket, index);
+                }
+            }
+        }
+        obj
+    }
+}
+
 impl Iterator for IntoIter {
     type Item = (IString, IValue);
 
@@ -522,7 +665,12 @@ pub struct IObject(pub(crate) IValue);
 
 value_subtype_impls!(IObject, into_object, as_object, as_object_mut);
 
-static EMPTY_HEADER: Header = Header { len: 0, cap: 0 };
+static EMPTY_HEADER: Header = Header {
+    #[cfg(feature = "snapshot")]
+    rc: AtomicUsize::new(0),
+    len: 0,
+    cap: 0,
+};
 
 impl IObject {
     fn layout(cap: usize) -> Result<Layout, LayoutError> {
@@ -537,7 +685,15 @@ impl IObject {
     fn alloc(cap: usize) -> *mut Header {
         unsafe {
             let hd = alloc(Self::layout(cap).unwrap()).cast::<Header>();
-            std::ptr::write(hd, Header { len: 0, cap });
+            std::ptr::write(
+                hd,
+                Header {
+                    #[cfg(feature = "snapshot")]
+                    rc: AtomicUsize::new(1),
+                    len: 0,
+                    cap,
+                },
+            );
             let mut hd_mut = ThinMut::new(hd);
             let hash_ptr = hd_mut.hashes_ptr_mut();
             for i in 0..hash_capacity(cap) {
@@ -571,24 +727,61 @@ impl IObject {
         }
     }
 
-    fn header(&self) -> ThinRef<Header> {
+    fn header(&self) -> ThinRef<'_, Header> {
         unsafe { ThinRef::new(self.0.ptr().cast()) }
     }
 
     // Safety: must not be static
-    unsafe fn header_mut(&mut self) -> ThinMut<Header> {
+    unsafe fn header_mut(&mut self) -> ThinMut<'_, Header> {
+        #[cfg(feature = "snapshot")]
+        self.make_unique();
         ThinMut::new(self.0.ptr().cast())
     }
 
     fn is_static(&self) -> bool {
         self.capacity() == 0
     }
+    #[cfg(feature = "snapshot")]
+    fn is_shared(&self) -> bool {
+        !self.is_static() && self.header().rc.load(AtomicOrdering::Acquire) > 1
+    }
+    // Copies the entries into a new allocation if any snapshots still refer to
+    // this one, so that it can be safely mutated.
+    #[cfg(feature = "snapshot")]
+    fn make_unique(&mut self) {
+        if self.is_shared() {
+            *self = self.copy_with_capacity(self.capacity());
+        }
+    }
+    // Values are snapshotted rather than cloned, so nested arrays and objects
+    // are only copied once they are themselves mutated.
+    #[cfg(feature = "snapshot")]
+    fn copy_with_capacity(&self, cap: usize) -> Self {
+        let mut res = Self::with_capacity(cap.max(self.len()));
+        for (k, v) in self.iter() {
+            res.insert(k.clone(), v.snapshot());
+        }
+        res
+    }
     /// Returns the capacity of the object. This is the maximum number of entries the object
     /// can hold without reallocating.
     #[must_use]
     pub fn capacity(&self) -> usize {
         self.header().cap
     }
+    /// Returns the size in bytes of the heap allocation backing this object, including
+    /// the header and hash table. This is zero for an empty object with no capacity, which
+    /// does not allocate.
+    ///
+    /// This does not include the memory used by the entries themselves.
+    #[must_use]
+    pub fn allocation_size(&self) -> usize {
+        if self.is_static() {
+            0
+        } else {
+            Self::layout(self.capacity()).unwrap().size()
+        }
+    }
     /// Returns the number of entries currently stored in the object.
     #[must_use]
     pub fn len(&self) -> usize {
@@ -601,6 +794,11 @@ impl IObject {
     }
 
     fn resize_internal(&mut self, cap: usize) {
+        #[cfg(feature = "snapshot")]
+        if self.is_shared() {
+            *self = self.copy_with_capacity(cap);
+            return;
+        }
         let old_obj = mem::replace(self, Self::with_capacity(cap));
         if !self.is_static() {
             unsafe {
@@ -626,15 +824,100 @@ impl IObject {
         self.resize_internal(cmp::max(current_capacity * 2, desired_capacity.max(4)));
     }
 
+    /// Ensures the object can hold `total_entries` entries in total without
+    /// reallocating.
+    ///
+    /// Unlike [`IObject::reserve`], this allocates space for exactly that many
+    /// entries rather than doubling the capacity, so it is preferable when the
+    /// final size of the object is known up-front.
+    pub fn reserve_for(&mut self, total_entries: usize) {
+        if self.capacity() < total_entries {
+            self.resize_internal(total_entries);
+        }
+    }
+
+    /// Builds an object from an iterator of fallible entries, stopping at the
+    /// first error. Later entries replace earlier entries with the same key.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error produced by `iter`, without consuming any
+    /// further entries.
+    pub fn try_from_iter<K: Into<IString>, V: Into<IValue>, E>(
+        iter: impl IntoIterator<Item = Result<(K, V), E>>,
+    ) -> Result<Self, E> {
+        let iter = iter.into_iter();
+        let mut res = IObject::with_capacity(iter.size_hint().0);
+        for item in iter {
+            let (k, v) = item?;
+            res.insert(k, v);
+        }
+        Ok(res)
+    }
+
+    /// Builds an object from entries whose keys are known to be distinct.
+    ///
+    /// This skips the check for an existing entry with the same key which
+    /// [`FromIterator`] does for every insertion. If `entries` does contain
+    /// duplicate keys, the resulting object will contain all of them and
+    /// which one is returned by lookups is unspecified. This is checked in
+    /// debug builds.
+    ///
+    /// # Panics
+    ///
+    /// Panics in debug builds if `entries` contains the same key twice.
+    pub fn from_unique_entries(entries: impl IntoIterator<Item = (IString, IValue)>) -> Self {
+        let iter = entries.into_iter();
+        let mut res = IObject::with_capacity(iter.size_hint().0);
+        for (key, value) in iter {
+            let key = key.into_interned();
+            res.reserve(1);
+            // Safety: we just reserved space for the entry, so the object is
+            // not static and the bucket returned is valid.
+            unsafe {
+                let mut hd = res.header_mut();
+                debug_assert!(
+                    hd.split().find_bucket(&key).is_err(),
+                    "duplicate key {:?}",
+                    key
+                );
+                let bucket = hd.split().find_vacant_bucket(&key);
+                let index = hd.push(key, value);
+                hd.split_mut().shift(bucket, index);
+            }
+        }
+        res
+    }
+
+    /// Inserts each entry from `iter` into the object, like [`Extend::extend`].
+    ///
+    /// Rather than reserving space for every item up-front, entries whose key
+    /// is already present are updated in place, and the object only grows
+    /// when a new key is inserted. This avoids growing the object far beyond
+    /// its final size when `iter` contains many duplicate keys.
+    pub fn extend_dedup<K: Into<IString>, V: Into<IValue>>(
+        &mut self,
+        iter: impl IntoIterator<Item = (K, V)>,
+    ) {
+        for (k, v) in iter {
+            let k = k.into();
+            if let Some(existing) = self.get_mut(&k) {
+                *existing = v.into();
+            } else {
+                self.insert(k, v);
+            }
+        }
+    }
+
     /// Returns a view of an entry within this object.
-    pub fn entry(&mut self, key: impl Into<IString>) -> Entry {
+    pub fn entry(&mut self, key: impl Into<IString>) -> Entry<'_> {
         self.reserve(1);
         // Safety: cannot be static after reserving space
         unsafe { self.header_mut().entry(key.into()) }
     }
     /// Returns a view of an entry within this object, whilst avoiding
     /// cloning the key if the entry is already occupied.
-    pub fn entry_or_clone(&mut self, key: &IString) -> Entry {
+    pub fn entry_or_clone(&mut self, key: &IString) -> Entry<'_> {
         self.reserve(1);
         // Safety: cannot be static after reserving space
         unsafe { self.header_mut().entry_or_clone(key) }
@@ -649,16 +932,105 @@ impl IObject {
     }
     /// Returns an iterator over (&key, &value) pairs in this object.
     #[must_use]
-    pub fn iter(&self) -> Iter {
+    pub fn iter(&self) -> Iter<'_> {
         Iter(self.header().split().items.iter())
     }
+    /// Returns an iterator over (position, &key, &value) triples in this
+    /// object, where each position is the one accepted by
+    /// [`IObject::get_index`].
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, &IString, &IValue)> {
+        self.iter().enumerate().map(|(i, (k, v))| (i, k, v))
+    }
+    /// Returns the (&key, &value) pair at the specified position within this
+    /// object, or `None` if the index is out of bounds.
+    ///
+    /// Positions follow the iteration order of the object, and are only stable
+    /// for as long as the object is not mutated.
+    #[must_use]
+    pub fn get_index(&self, index: usize) -> Option<(&IString, &IValue)> {
+        self.header()
+            .split()
+            .items
+            .get(index)
+            .map(|item| (&item.key, &item.value))
+    }
+    /// Returns the (&key, &mut value) pair at the specified position within
+    /// this object, or `None` if the index is out of bounds. See
+    /// [`IObject::get_index`].
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&IString, &mut IValue)> {
+        if index < self.len() {
+            // Safety: not static
+            let item = unsafe { self.header_mut().split_mut().items.get_mut(index) };
+            item.map(|item| (&item.key, &mut item.value))
+        } else {
+            None
+        }
+    }
+    /// Returns the first (&key, &value) pair in this object, or `None` if it is
+    /// empty.
+    #[must_use]
+    pub fn first(&self) -> Option<(&IString, &IValue)> {
+        self.get_index(0)
+    }
+    /// Returns the last (&key, &value) pair in this object, or `None` if it is
+    /// empty.
+    #[must_use]
+    pub fn last(&self) -> Option<(&IString, &IValue)> {
+        self.len()
+            .checked_sub(1)
+            .and_then(|index| self.get_index(index))
+    }
+    /// Returns the first (&key, &mut value) pair in this object, or `None` if it
+    /// is empty.
+    pub fn first_mut(&mut self) -> Option<(&IString, &mut IValue)> {
+        self.iter_mut().next()
+    }
+    /// Returns the last (&key, &mut value) pair in this object, or `None` if it
+    /// is empty.
+    pub fn last_mut(&mut self) -> Option<(&IString, &mut IValue)> {
+        if self.is_empty() {
+            None
+        } else {
+            // Safety: not static
+            let item = unsafe { self.header_mut().split_mut().items.last_mut() };
+            item.map(|item| (&item.key, &mut item.value))
+        }
+    }
+    /// Returns the positions of the entries in this object, ordered by key.
+    ///
+    /// The result can be cached and used with [`IObject::get_index`] to
+    /// repeatedly visit the entries in sorted order without sorting them
+    /// again, provided the object is not mutated in the meantime.
+    #[must_use]
+    pub fn sorted_key_indices(&self) -> Vec<usize> {
+        let items = self.header().split().items;
+        let mut indices: Vec<usize> = (0..items.len()).collect();
+        // Keys are unique, so an unstable sort is sufficient
+        indices.sort_unstable_by(|&a, &b| items[a].key.cmp(&items[b].key));
+        indices
+    }
+    /// Returns an iterator over references to the values in this object,
+    /// ordered by their keys.
+    pub fn values_sorted_by_key(&self) -> impl Iterator<Item = &IValue> {
+        let items = self.header().split().items;
+        self.sorted_key_indices()
+            .into_iter()
+            .map(move |index| &items[index].value)
+    }
     /// Returns an iterator over mutable references to the values in
     /// this object.
     pub fn values_mut(&mut self) -> impl Iterator<Item = &mut IValue> {
         self.iter_mut().map(|x| x.1)
     }
+    /// Replaces each value in this object with the result of calling `f` on it.
+    /// The keys and their order are unchanged.
+    pub fn map_values(&mut self, mut f: impl FnMut(&IValue) -> IValue) {
+        for v in self.values_mut() {
+            *v = f(v);
+        }
+    }
     /// Returns an iterator over (&key, &mut value) pairs in this object.
-    pub fn iter_mut(&mut self) -> IterMut {
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
         IterMut(
             if self.is_empty() {
                 &mut []
@@ -697,6 +1069,56 @@ impl IObject {
         self.get_key_value(k).map(|x| x.1)
     }
 
+    /// Looks up the specified key in this object and returns a reference to the
+    /// corresponding value if found.
+    ///
+    /// Unlike [`IObject::get`], this never interns `key`, so looking up a key
+    /// which is not present does not touch the global string cache.
+    #[must_use]
+    pub fn get_str(&self, key: &str) -> Option<&IValue> {
+        self.get(&IString::lookup(key)?)
+    }
+
+    /// Looks up the specified key in this object and returns a reference to the
+    /// interned key stored in the object, if found. Like [`IObject::get_str`],
+    /// this never interns `key`.
+    ///
+    /// This allows the key to be reused elsewhere without interning it again.
+    #[must_use]
+    pub fn get_key(&self, key: &str) -> Option<&IString> {
+        self.get_key_value(&IString::lookup(key)?).map(|x| x.0)
+    }
+
+    /// Looks up each of the specified keys in this object, returning a reference
+    /// to the corresponding value (or `None`) for each one, in the same order.
+    /// Like [`IObject::get_str`], this never interns the keys.
+    #[must_use]
+    pub fn get_all(&self, keys: &[&str]) -> Vec<Option<&IValue>> {
+        keys.iter().map(|k| self.get_str(k)).collect()
+    }
+
+    /// Returns the position of the specified key within this object, such that
+    /// [`IObject::get_index`] returns its entry. Like [`IObject::get_str`], this
+    /// never interns `key`.
+    #[must_use]
+    pub fn position_of(&self, key: &str) -> Option<usize> {
+        let key = IString::lookup(key)?;
+        if self.is_empty() {
+            return None;
+        }
+        let hd = self.header().split();
+        let bucket = hd.find_bucket(&key).ok()?;
+        // Safety: Bucket index is valid
+        Some(unsafe { *hd.table.get_unchecked(bucket) })
+    }
+
+    /// Returns `true` if this object contains the specified key, without
+    /// interning it. See [`IObject::get_str`].
+    #[must_use]
+    pub fn contains_str(&self, key: &str) -> bool {
+        self.get_str(key).is_some()
+    }
+
     /// Looks up the specified key in this object and returns a mutable reference to
     /// the corresponding value if found.
     pub fn get_mut(&mut self, k: impl ObjectIndex) -> Option<&mut IValue> {
@@ -711,6 +1133,13 @@ impl IObject {
     /// Inserts a new value into this object with the specified key. If a value already
     /// existed at this key, that value is replaced and returend.
     pub fn insert(&mut self, k: impl Into<IString>, v: impl Into<IValue>) -> Option<IValue> {
+        self.insert_value(k.into(), v.into())
+    }
+
+    /// Inserts a new value into this object like [`IObject::insert`], but
+    /// without any conversions, to avoid generating a copy of this function
+    /// for every key and value type.
+    pub fn insert_value(&mut self, k: IString, v: IValue) -> Option<IValue> {
         match self.entry(k) {
             Entry::Occupied(mut occ) => Some(occ.insert(v)),
             Entry::Vacant(vac) => {
@@ -737,6 +1166,27 @@ impl IObject {
         self.resize_internal(self.len());
     }
 
+    /// Shrinks the memory allocation used by the object such that its
+    /// capacity is at least `min_capacity`, and at least its length.
+    ///
+    /// Does nothing if the capacity is already that small.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let cap = cmp::max(self.len(), min_capacity);
+        if self.capacity() > cap {
+            self.resize_internal(cap);
+        }
+    }
+
+    /// Removes every entry whose key is not present in `other`.
+    pub fn retain_keys_in(&mut self, other: &IObject) {
+        self.retain(|k, _| other.contains_key(k));
+    }
+
+    /// Removes every entry whose key is present in `other`.
+    pub fn remove_keys_in(&mut self, other: &IObject) {
+        self.retain(|k, _| !other.contains_key(k));
+    }
+
     /// Calls the specified function for each entry in the object. Each entry
     /// where the function returns `false` is removed from the object.
     ///
@@ -764,6 +1214,96 @@ impl IObject {
         }
     }
 
+    /// Like [`IObject::retain`], but returns the number of entries which were
+    /// removed.
+    pub fn retain_count(&mut self, f: impl FnMut(&IString, &mut IValue) -> bool) -> usize {
+        let len = self.len();
+        self.retain(f);
+        len - self.len()
+    }
+
+    /// Rebuilds the object by passing each entry, in order, to the function
+    /// provided, and inserting every entry it returns in its place.
+    ///
+    /// This allows an entry to be renamed, removed, or split into several
+    /// entries. If the same key is produced more than once, the last value
+    /// produced for it is kept.
+    pub fn transform_entries(
+        &mut self,
+        mut f: impl FnMut(IString, IValue) -> Vec<(IString, IValue)>,
+    ) {
+        let old_obj = mem::replace(self, Self::with_capacity(self.len()));
+        for (k, v) in old_obj {
+            for (k, v) in f(k, v) {
+                self.insert(k, v);
+            }
+        }
+    }
+
+    /// Deep-merges `other` into this object, summing numbers which are present
+    /// at the same key on both sides.
+    ///
+    /// Nested objects are merged recursively. Any other conflict is resolved as
+    /// in a JSON merge patch (RFC 7386): the value from `other` replaces the
+    /// existing value, and a `null` in `other` removes the key.
+    ///
+    /// Sums of integers are exact for as long as they fit in an `i64` or `u64`,
+    /// and are otherwise computed as `f64`. A sum too large for an `f64`
+    /// saturates at `f64::MAX` (or `f64::MIN`), rather than losing the total.
+    pub fn add_assign_deep(&mut self, other: &IObject) {
+        for (k, v) in other {
+            if v.is_null() {
+                self.remove(k);
+                continue;
+            }
+            let dest = &mut self[k];
+            if let Some(other_obj) = v.as_object() {
+                if !dest.is_object() {
+                    *dest = IObject::new().into();
+                }
+                dest.as_object_mut().unwrap().add_assign_deep(other_obj);
+            } else if let (Some(a), Some(b)) = (dest.as_number(), v.as_number()) {
+                *dest = a.saturating_add(b).into();
+            } else {
+                *dest = v.clone();
+            }
+        }
+    }
+
+    /// Returns a cheap, structurally-shared snapshot of this object.
+    ///
+    /// This works in the same way as [`IArray::snapshot`]: the allocation is
+    /// shared until either side is mutated, at which point it is copied.
+    ///
+    /// Requires the `snapshot` feature.
+    ///
+    /// [`IArray::snapshot`]: super::IArray::snapshot
+    #[cfg(feature = "snapshot")]
+    #[must_use]
+    pub fn snapshot(&self) -> Self {
+        if !self.is_static() {
+            self.header().rc.fetch_add(1, AtomicOrdering::Relaxed);
+        }
+        IObject(unsafe { self.0.raw_copy() })
+    }
+
+    // If the keys match, the values are overwritten in place so that any
+    // allocations they own can be reused. Otherwise only the allocation of
+    // the object itself is reused.
+    pub(crate) fn clone_from_impl(&mut self, source: &IObject) {
+        if self.keys().eq(source.keys()) {
+            for ((_, dest), (_, v)) in self.iter_mut().zip(source) {
+                dest.clone_from(v);
+            }
+        } else {
+            self.clear();
+            self.reserve(source.len());
+            for (k, v) in source {
+                self.insert(k.clone(), v.clone());
+            }
+        }
+    }
+
     pub(crate) fn clone_impl(&self) -> IValue {
         let mut res = Self::with_capacity(self.len());
         for (k, v) in self.iter() {
@@ -773,6 +1313,15 @@ impl IObject {
         res.0
     }
     pub(crate) fn drop_impl(&mut self) {
+        #[cfg(feature = "snapshot")]
+        if !self.is_static() {
+            if self.header().rc.fetch_sub(1, AtomicOrdering::Release) != 1 {
+                // Other snapshots still refer to this allocation
+                unsafe { self.0.set_ref(&EMPTY_HEADER) };
+                return;
+            }
+            fence(AtomicOrdering::Acquire);
+        }
         self.clear();
         if !self.is_static() {
             unsafe {
@@ -830,9 +1379,14 @@ impl Hash for IObject {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.len().hash(state);
 
+        // Entry hashes are summed, so that the result does not depend on the
+        // order of the entries. Each entry is hashed with a hasher seeded from
+        // `state`, so that which objects collide depends on the caller's seed.
+        let mut seeded = DefaultHasher::new();
+        seeded.write_u64(state.finish());
         let mut total_hash = 0_u64;
         for item in self.iter() {
-            let mut h = DefaultHasher::new();
+            let mut h = seeded.clone();
             item.hash(&mut h);
             total_hash = total_hash.wrapping_add(h.finish());
         }
@@ -880,6 +1434,8 @@ mod private {
     impl Sealed for usize {}
     impl Sealed for &str {}
     impl Sealed for &super::IString {}
+    impl Sealed for &String {}
+    impl Sealed for &std::borrow::Cow<'_, str> {}
     impl<T: Sealed> Sealed for &T {}
 }
 
@@ -917,6 +1473,33 @@ impl ObjectIndex for &str {
     }
 }
 
+// Other string types are indexed via `&str`.
+macro_rules! impl_object_index_via_str {
+    ($($t:ty),*) => {
+        $(
+            impl ObjectIndex for $t {
+                fn index_into(self, v: &IObject) -> Option<(&IString, &IValue)> {
+                    (&**self).index_into(v)
+                }
+
+                fn index_into_mut(self, v: &mut IObject) -> Option<(&IString, &mut IValue)> {
+                    (&**self).index_into_mut(v)
+                }
+
+                fn index_or_insert(self, v: &mut IObject) -> &mut IValue {
+                    (&**self).index_or_insert(v)
+                }
+
+                fn remove(self, v: &mut IObject) -> Option<(IString, IValue)> {
+                    (&**self).remove(v)
+                }
+            }
+        )*
+    };
+}
+
+impl_object_index_via_str!(&String, &Cow<'_, str>);
+
 impl ObjectIndex for &IString {
     fn index_into(self, v: &IObject) -> Option<(&IString, &IValue)> {
         if v.is_empty() {
@@ -1032,6 +1615,9 @@ impl<'a> Iterator for IterMut<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         self.0.next().map(|x| (&x.key, &mut x.value))
     }
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.0.nth(n).map(|x| (&x.key, &mut x.value))
+    }
 }
 
 impl ExactSizeIterator for IterMut<'_> {
@@ -1172,6 +1758,156 @@ mod tests {
         assert_eq!(y["c"], IValue::FALSE);
     }
 
+    #[mockalloc::test]
+    fn can_add_assign_deep() {
+        let mut x: IObject = ijson!({
+            "hits": 3,
+            "bytes": 1.5,
+            "big": 9_223_372_036_854_775_807_i64,
+            "routes": { "/": 1, "/api": 2 },
+            "status": "ok",
+            "gone": 1
+        })
+        .into_object()
+        .unwrap();
+        let y: IObject = ijson!({
+            "hits": 4,
+            "bytes": 2,
+            "big": 1,
+            "routes": { "/api": 5, "/new": 1 },
+            "status": 7,
+            "gone": null,
+            "extra": "x"
+        })
+        .into_object()
+        .unwrap();
+
+        x.add_assign_deep(&y);
+
+        let expected: IObject = ijson!({
+            "hits": 7,
+            "bytes": 3.5,
+            "big": 9_223_372_036_854_775_808_u64,
+            "routes": { "/": 1, "/api": 7, "/new": 1 },
+            "status": 7,
+            "extra": "x"
+        })
+        .into_object()
+        .unwrap();
+        assert_eq!(x, expected);
+        assert!(x["bytes"].as_number().unwrap().has_decimal_point());
+        assert!(!x["hits"].as_number().unwrap().has_decimal_point());
+    }
+
+    #[mockalloc::test]
+    fn add_assign_deep_saturates() {
+        let mut x: IObject = ijson!({ "up": f64::MAX, "down": f64::MIN, "u": u64::MAX })
+            .into_object()
+            .unwrap();
+        let y: IObject = ijson!({ "up": f64::MAX, "down": -1e300, "u": u64::MAX })
+            .into_object()
+            .unwrap();
+
+        x.add_assign_deep(&y);
+        x.add_assign_deep(&y);
+
+        assert_eq!(x["up"].to_f64(), Some(f64::MAX));
+        assert_eq!(x["down"].to_f64(), Some(f64::MIN));
+        assert_eq!(x["u"].to_f64(), Some(3.0 * u64::MAX as f64));
+    }
+
+    #[mockalloc::test]
+    fn can_visit_sorted_key_indices() {
+        let mut x = IObject::new();
+        for k in &["d", "b", "e", "a", "c"] {
+            x.insert(*k, IValue::NULL);
+        }
+        x.remove("e");
+
+        let indices = x.sorted_key_indices();
+        let keys: Vec<&str> = indices
+            .iter()
+            .map(|&i| x.get_index(i).unwrap().0.as_str())
+            .collect();
+
+        assert_eq!(keys, ["a", "b", "c", "d"]);
+        assert_eq!(x.get_index(x.len()), None);
+    }
+
+    #[mockalloc::test]
+    fn can_iterate_values_sorted_by_key() {
+        let mut x = IObject::new();
+        x.insert("c", 3);
+        x.insert("a", 1);
+        x.insert("d", 4);
+        x.insert("b", 2);
+
+        let values: Vec<_> = x.values_sorted_by_key().cloned().collect();
+
+        assert_eq!(values, [1, 2, 3, 4].map(IValue::from));
+    }
+
+    #[mockalloc::test]
+    fn can_get_str_without_interning() {
+        let mut x = IObject::new();
+        x.insert("get_str_present", IValue::TRUE);
+
+        assert_eq!(x.get_str("get_str_present"), Some(&IValue::TRUE));
+        assert!(x.contains_str("get_str_present"));
+        assert_eq!(x.get_str("get_str_never_interned"), None);
+        assert!(!x.contains_str("get_str_never_interned"));
+        assert!(IString::lookup("get_str_never_interned").is_none());
+    }
+
+    #[mockalloc::test]
+    fn can_transform_entries() {
+        let mut x = IObject::new();
+        x.insert("x", 1);
+        x.insert("y", 2);
+        x.transform_entries(|k, v| {
+            vec![
+                (format!("{}_a", k.as_str()).into(), v.clone()),
+                (format!("{}_b", k.as_str()).into(), v),
+            ]
+        });
+
+        let keys: Vec<&str> = x.keys().map(IString::as_str).collect();
+        assert_eq!(keys, ["x_a", "x_b", "y_a", "y_b"]);
+        assert_eq!(x["x_b"], IValue::from(1));
+        assert_eq!(x["y_a"], IValue::from(2));
+    }
+
+    #[mockalloc::test]
+    fn can_get_key() {
+        let mut x = IObject::new();
+        x.insert(String::from("get_key"), IValue::TRUE);
+
+        let interned = IString::intern("get_key");
+        let key = x.get_key("get_key").unwrap();
+        assert_eq!(key, &interned);
+        assert_eq!(key.as_str().as_ptr(), interned.as_str().as_ptr());
+        assert_eq!(x.get_key("get_key_missing"), None);
+    }
+
+    #[cfg(feature = "snapshot")]
+    #[mockalloc::test]
+    fn snapshot_is_independent() {
+        let mut x = IObject::new();
+        x.insert("a", IValue::NULL);
+        x.insert("b", IValue::TRUE);
+        let y = x.snapshot();
+
+        x.insert("c", IValue::FALSE);
+        x.remove("a");
+        *x.get_mut("b").unwrap() = IValue::FALSE;
+
+        assert_eq!(x.len(), 2);
+        assert_eq!(x["b"], IValue::FALSE);
+        assert_eq!(y.len(), 2);
+        assert_eq!(y["a"], IValue::NULL);
+        assert_eq!(y["b"], IValue::TRUE);
+    }
+
     // Too slow for miri
     #[cfg(not(miri))]
     #[mockalloc::test]
@@ -1197,4 +1933,522 @@ mod tests {
             assert_eq!(x, IObject::new());
         }
     }
+
+    #[mockalloc::test]
+    fn can_get_allocation_size() {
+        assert_eq!(IObject::new().allocation_size(), 0);
+        for cap in [1, 4, 17].iter().copied() {
+            let x = IObject::with_capacity(cap);
+            assert_eq!(x.allocation_size(), IObject::layout(cap).unwrap().size());
+            assert!(
+                x.allocation_size()
+                    > std::mem::size_of::<Header>() + cap * std::mem::size_of::<KeyValuePair>()
+            );
+        }
+    }
+
+    #[mockalloc::test]
+    fn can_extend_with_duplicate_keys() {
+        let items = || (0..100).map(|i| (["a", "b", "c"][i % 3], i));
+        let mut x = IObject::new();
+        x.extend_dedup(items());
+        let mut y = IObject::new();
+        y.extend(items());
+
+        assert_eq!(x, y);
+        assert_eq!(x.len(), 3);
+        assert_eq!(x.capacity(), 4);
+        assert!(y.capacity() >= 100);
+
+        x.extend_dedup(items().chain(Some(("d", 0))));
+        assert_eq!(x.capacity(), 4);
+        assert_eq!(IValue::from(x), ijson!({"a": 99, "b": 97, "c": 98, "d": 0}));
+    }
+
+    #[test]
+    fn extend_dedup_only_allocates_the_table() {
+        let keys: Vec<IString> = ["a", "b", "c", "d"].iter().map(|&k| k.into()).collect();
+        let items = || (0..100).map(|i| (keys[i % 3].clone(), i));
+        let mut x = IObject::new();
+        let info = mockalloc::record_allocs(|| {
+            x.extend_dedup(items());
+            x.extend_dedup(items().chain(Some((keys[3].clone(), 0))));
+        });
+
+        assert_eq!(info.num_allocs(), 1);
+        assert_eq!(
+            info.mem_allocated(),
+            IObject::layout(4).unwrap().size() as u64
+        );
+        assert_eq!(x.len(), 4);
+    }
+
+    #[cfg(feature = "direct_strings")]
+    #[mockalloc::test]
+    fn interns_direct_keys() {
+        let mut x = IObject::new();
+        assert!(x.get(&IString::direct("a")).is_none());
+        x.insert(IString::direct("a"), 1);
+        x.entry_or_clone(&IString::direct("b"))
+            .or_insert(IValue::from(2));
+        x.extend_dedup(vec![(IString::direct("a"), 3)]);
+
+        assert!(x.keys().all(IString::is_interned));
+        assert_eq!(x.get(&IString::direct("a")), Some(&IValue::from(3)));
+        assert_eq!(x.remove(&IString::direct("b")), Some(IValue::from(2)));
+        assert!(x.get(&IString::direct("c")).is_none());
+        assert_eq!(x, ijson!({ "a": 3 }).into_object().unwrap());
+    }
+
+    #[mockalloc::test]
+    fn can_index_with_owned_strings() {
+        let mut x = IObject::new();
+        x.insert("foo", 1);
+        let owned = String::from("foo");
+        let cow: Cow<str> = Cow::Owned("bar".into());
+
+        assert_eq!(x.get(&owned), Some(&IValue::from(1)));
+        assert_eq!(x[&owned], IValue::from(1));
+        assert!(x.get(&cow).is_none());
+        x[&cow] = true.into();
+        assert_eq!(x.remove(&cow), Some(IValue::TRUE));
+        assert_eq!(x.remove(&owned), Some(IValue::from(1)));
+        assert!(x.is_empty());
+    }
+
+    #[mockalloc::test]
+    fn can_retain_and_remove_keys_in() {
+        let x: IObject = ijson!({"a": 1, "b": 2, "c": 3}).into_object().unwrap();
+        let other: IObject = ijson!({"b": null, "c": null, "d": null})
+            .into_object()
+            .unwrap();
+
+        let mut intersection = x.clone();
+        intersection.retain_keys_in(&other);
+        let mut difference = x;
+        difference.remove_keys_in(&other);
+
+        assert_eq!(IValue::from(intersection), ijson!({"b": 2, "c": 3}));
+        assert_eq!(IValue::from(difference), ijson!({"a": 1}));
+    }
+
+    #[mockalloc::test]
+    fn can_get_first_and_last() {
+        let mut x: IObject = ijson!({"a": 1, "b": 2, "c": 3}).into_object().unwrap();
+        assert_eq!(x.first(), Some((&IString::intern("a"), &IValue::from(1))));
+        assert_eq!(x.last(), Some((&IString::intern("c"), &IValue::from(3))));
+
+        *x.first_mut().unwrap().1 = true.into();
+        *x.last_mut().unwrap().1 = false.into();
+        x.remove("b");
+        assert_eq!(IValue::from(x), ijson!({"a": true, "c": false}));
+
+        let mut empty = IObject::new();
+        assert!(empty.first().is_none());
+        assert!(empty.last().is_none());
+        assert!(empty.first_mut().is_none());
+        assert!(empty.last_mut().is_none());
+    }
+
+    #[mockalloc::test]
+    fn can_map_values() {
+        let mut x: IObject = ijson!({"b": 1, "a": 2, "c": "d"}).into_object().unwrap();
+        x.map_values(|v| match v.to_i64() {
+            Some(n) => (n + 1).into(),
+            None => v.clone(),
+        });
+
+        let keys: Vec<_> = x.keys().map(IString::as_str).collect();
+        assert_eq!(keys, ["b", "a", "c"]);
+        assert_eq!(IValue::from(x), ijson!({"b": 2, "a": 3, "c": "d"}));
+    }
+
+    #[test]
+    fn can_reserve_for_total_entries() {
+        let keys: Vec<IString> = (0..10).map(|i| IString::intern(&i.to_string())).collect();
+        let mut x = IObject::new();
+        x.insert(keys[0].clone(), 0);
+        let info = mockalloc::record_allocs(|| {
+            x.reserve_for(10);
+            for (i, k) in keys.iter().enumerate() {
+                x.insert(k.clone(), i);
+            }
+        });
+
+        assert_eq!(info.num_allocs(), 1);
+        assert_eq!(x.capacity(), 10);
+        assert_eq!(x.len(), 10);
+        assert_eq!(x.allocation_size(), IObject::layout(10).unwrap().size());
+
+        x.reserve_for(5);
+        assert_eq!(x.capacity(), 10);
+    }
+
+    #[mockalloc::test]
+    fn can_get_position_of_key() {
+        let mut x = IObject::new();
+        for k in &["c", "a", "d", "b"] {
+            x.insert(*k, IValue::NULL);
+        }
+
+        for (index, (k, _)) in x.iter().enumerate() {
+            assert_eq!(x.position_of(k), Some(index));
+            assert_eq!(x.get_index(index).unwrap().0, k);
+        }
+        assert_eq!(x.position_of("d"), Some(2));
+        assert_eq!(x.position_of("missing"), None);
+        assert_eq!(IObject::new().position_of("a"), None);
+    }
+
+    #[mockalloc::test]
+    fn can_get_all() {
+        let x: IObject = ijson!({"a": 1, "b": 2, "c": 3}).into_object().unwrap();
+
+        assert_eq!(
+            x.get_all(&["c", "missing", "a", "c"]),
+            [
+                Some(&IValue::from(3)),
+                None,
+                Some(&IValue::from(1)),
+                Some(&IValue::from(3))
+            ]
+        );
+        assert!(x.get_all(&[]).is_empty());
+    }
+
+    #[mockalloc::test]
+    fn can_insert_containers_into_entries() {
+        let mut x = IObject::new();
+        for i in 1..=3 {
+            x.entry("list")
+                .or_insert_array()
+                .as_array_mut()
+                .unwrap()
+                .push(i);
+        }
+        x.entry("nested")
+            .or_insert_object()
+            .as_object_mut()
+            .unwrap()
+            .insert("a", 1);
+        x.entry("nested")
+            .or_insert_object()
+            .as_object_mut()
+            .unwrap()
+            .insert("b", 2);
+        assert!(x.entry("default").or_default().is_null());
+
+        assert_eq!(
+            IValue::from(x),
+            ijson!({"list": [1, 2, 3], "nested": {"a": 1, "b": 2}, "default": null})
+        );
+    }
+
+    #[mockalloc::test]
+    fn can_build_from_unique_entries() {
+        let keys: Vec<IString> = (0..100).map(|i| format!("unique-{}", i).into()).collect();
+        let x = IObject::from_unique_entries(
+            keys.iter()
+                .enumerate()
+                .map(|(i, k)| (k.clone(), IValue::from(i))),
+        );
+        let y: IObject = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (k.clone(), i))
+            .collect();
+
+        assert_eq!(x, y);
+        for (i, k) in keys.iter().enumerate() {
+            assert_eq!(x[k], IValue::from(i));
+        }
+        assert!(IObject::from_unique_entries(Vec::new()).is_empty());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "duplicate key")]
+    fn unique_entries_rejects_duplicates() {
+        IObject::from_unique_entries(vec![("a".into(), IValue::NULL), ("a".into(), IValue::NULL)]);
+    }
+
+    #[mockalloc::test]
+    fn can_shrink_to() {
+        let mut x = IObject::with_capacity(20);
+        for i in 0..5 {
+            x.insert(format!("shrink-{}", i), i);
+        }
+        x.shrink_to(10);
+        assert_eq!(x.capacity(), 10);
+        x.shrink_to(15);
+        assert_eq!(x.capacity(), 10);
+        x.shrink_to(0);
+        assert_eq!(x.capacity(), 5);
+        assert_eq!(x.len(), 5);
+    }
+
+    #[mockalloc::test]
+    fn can_iter_indexed() {
+        let mut x = IObject::new();
+        x.insert("c", 1);
+        x.insert("a", 2);
+        x.insert("b", 3);
+
+        let entries: Vec<_> = x.iter_indexed().map(|(i, k, _)| (i, k.as_str())).collect();
+        assert_eq!(entries, [(0, "c"), (1, "a"), (2, "b")]);
+        for (i, k, v) in x.iter_indexed() {
+            assert_eq!(x.get_index(i), Some((k, v)));
+            assert_eq!(x.position_of(k), Some(i));
+        }
+    }
+
+    #[mockalloc::test]
+    fn can_count_retained() {
+        let mut x: IObject = (0..10).map(|i| (format!("retain-{}", i), i)).collect();
+
+        assert_eq!(x.retain_count(|_, v| v.to_i32().unwrap() % 3 == 0), 6);
+        assert_eq!(x.len(), 4);
+        assert_eq!(x.retain_count(|_, _| true), 0);
+    }
+
+    #[mockalloc::test]
+    fn can_insert_value() {
+        let mut x = IObject::new();
+        let mut y = IObject::new();
+        for i in 0..10 {
+            let key = format!("insert-value-{}", i % 7);
+            assert_eq!(
+                x.insert(key.as_str(), i),
+                y.insert_value(key.into(), i.into())
+            );
+        }
+
+        assert_eq!(x, y);
+        assert_eq!(y.len(), 7);
+    }
+
+    #[mockalloc::test]
+    fn can_recover_object_from_into_iter() {
+        let x: IObject = (0..5).map(|i| (format!("recover-{}", i), i)).collect();
+        let cap = x.capacity();
+        let mut iter = x.into_iter();
+        assert_eq!(iter.next().unwrap().0.as_str(), "recover-0");
+        assert_eq!(iter.next().unwrap().0.as_str(), "recover-1");
+
+        let mut y = iter.into_object();
+        assert_eq!(y.capacity(), cap);
+        let keys: Vec<_> = y.keys().map(IString::as_str).collect();
+        assert_eq!(keys, ["recover-2", "recover-3", "recover-4"]);
+        for i in 2..5 {
+            assert_eq!(y[format!("recover-{}", i).as_str()], IValue::from(i));
+        }
+        assert!(y.get("recover-0").is_none());
+        y.insert("recover-5", 5);
+        assert_eq!(y.remove("recover-3"), Some(IValue::from(3)));
+        assert_eq!(y.len(), 3);
+
+        assert!(IObject::new().into_iter().into_object().is_empty());
+    }
+
+    #[mockalloc::test]
+    fn can_insert_with_key() {
+        let mut x = IObject::new();
+        x.insert("b", 1);
+        for key in ["a", "b", "c"] {
+            x.entry(key).or_insert_with_key(|k| k.to_uppercase().into());
+        }
+
+        assert_eq!(IValue::from(x), ijson!({"b": 1, "a": "A", "c": "C"}));
+    }
+
+    #[mockalloc::test]
+    fn can_get_index_mut() {
+        let mut x = IObject::new();
+        x.insert("a", 1);
+        x.insert("b", 2);
+
+        let (k, v) = x.get_index_mut(1).unwrap();
+        assert_eq!(k.as_str(), "b");
+        *v = IValue::from(20);
+        assert_eq!(x.get("b"), Some(&IValue::from(20)));
+        assert!(x.get_index_mut(2).is_none());
+        assert!(IObject::new().get_index_mut(0).is_none());
+    }
+
+    #[mockalloc::test]
+    fn can_try_from_iter() {
+        let ok: Result<IObject, ()> = IObject::try_from_iter(vec![Ok(("a", 1)), Ok(("b", 2))]);
+        assert_eq!(IValue::from(ok.unwrap()), ijson!({"a": 1, "b": 2}));
+
+        let mut consumed = 0;
+        let entries = (0..5).map(|i| {
+            consumed += 1;
+            if i == 2 {
+                Err(i)
+            } else {
+                Ok((format!("try-{}", i), i))
+            }
+        });
+        assert_eq!(IObject::try_from_iter(entries), Err(2));
+        assert_eq!(consumed, 3);
+    }
+
+    #[mockalloc::test]
+    fn hash_uses_outer_seed() {
+        use std::collections::hash_map::RandomState;
+        use std::hash::BuildHasher;
+
+        let hash = |obj: &IObject, seed: &RandomState| seed.hash_one(obj);
+        let x: IObject = (0..10).map(|i| (format!("seeded-{}", i), i)).collect();
+        let y: IObject = (0..10)
+            .rev()
+            .map(|i| (format!("seeded-{}", i), i))
+            .collect();
+        let (seed1, seed2) = (RandomState::new(), RandomState::new());
+
+        assert_eq!(hash(&x, &seed1), hash(&y, &seed1));
+        assert_eq!(hash(&x, &seed2), hash(&y, &seed2));
+        assert_ne!(hash(&x, &seed1), hash(&x, &seed2));
+    }
+
+    #[test]
+    fn hash_does_not_allocate() {
+        let x: IValue = ijson!({ "a": { "b": [1, { "c": null }] }, "d": "e" });
+        let x = x.into_object().unwrap();
+        let info = mockalloc::record_allocs(|| {
+            let mut h = DefaultHasher::new();
+            x.hash(&mut h);
+        });
+
+        assert_eq!(info.num_allocs(), 0);
+    }
+
+    // Hashes keys by their leading digit, so that keys sharing a digit collide.
+    fn hash_by_digit(s: &IString) -> usize {
+        (s.as_bytes()[0] - b'0') as usize
+    }
+
+    // Returns the key in each bucket of the hash table.
+    fn bucket_layout(obj: &IObject) -> Vec<Option<&str>> {
+        let split = obj.header().split();
+        split
+            .table
+            .iter()
+            .map(|&index| (index != usize::MAX).then(|| split.items[index].key.as_str()))
+            .collect()
+    }
+
+    #[mockalloc::test]
+    fn probes_displace_colliding_keys() {
+        with_hash_fn(hash_by_digit, || {
+            // A capacity of 8 gives 10 buckets
+            let mut x = IObject::with_capacity(8);
+            for k in &["1a", "1b", "1c", "2a", "3a"] {
+                x.insert(*k, true);
+            }
+            assert_eq!(
+                bucket_layout(&x),
+                [
+                    None,
+                    Some("1a"),
+                    Some("1b"),
+                    Some("1c"),
+                    Some("2a"),
+                    Some("3a"),
+                    None,
+                    None,
+                    None,
+                    None
+                ]
+            );
+
+            // "1d" has probed further than "2a", so takes its bucket and
+            // shifts the following keys along
+            x.insert("1d", true);
+            assert_eq!(
+                bucket_layout(&x),
+                [
+                    None,
+                    Some("1a"),
+                    Some("1b"),
+                    Some("1c"),
+                    Some("1d"),
+                    Some("2a"),
+                    Some("3a"),
+                    None,
+                    None,
+                    None
+                ]
+            );
+
+            // Removing a key shifts displaced keys back towards their ideal
+            // bucket, stopping at the first key already in its ideal bucket
+            x.insert("7a", true);
+            x.remove("1a");
+            assert_eq!(
+                bucket_layout(&x),
+                [
+                    None,
+                    Some("1b"),
+                    Some("1c"),
+                    Some("1d"),
+                    Some("2a"),
+                    Some("3a"),
+                    None,
+                    Some("7a"),
+                    None,
+                    None
+                ]
+            );
+            for k in &["1b", "1c", "1d", "2a", "3a", "7a"] {
+                assert!(x.contains_key(k));
+            }
+            assert!(!x.contains_key("1a"));
+            assert!(!x.contains_key("2b"));
+        });
+    }
+
+    #[mockalloc::test]
+    fn probes_wrap_around_table() {
+        with_hash_fn(hash_by_digit, || {
+            let mut x = IObject::with_capacity(8);
+            for k in &["9a", "9b", "9c", "0a"] {
+                x.insert(*k, true);
+            }
+            assert_eq!(
+                bucket_layout(&x),
+                [
+                    Some("9b"),
+                    Some("9c"),
+                    Some("0a"),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some("9a")
+                ]
+            );
+
+            x.remove("9a");
+            assert_eq!(
+                bucket_layout(&x),
+                [
+                    Some("9c"),
+                    Some("0a"),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some("9b")
+                ]
+            );
+            assert!(x.contains_key("9c"));
+            assert!(x.contains_key("0a"));
+        });
+    }
 }
@@ -0,0 +1,404 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::value::{DestructuredMut, DestructuredRef, IValue};
+
+// Splits a JSON Pointer into its unescaped reference tokens. Returns `None`
+// if the pointer is non-empty and does not start with a `/`.
+fn tokens(pointer: &str) -> Option<impl Iterator<Item = Cow<'_, str>>> {
+    let rest = if pointer.is_empty() {
+        None
+    } else {
+        Some(pointer.strip_prefix('/')?)
+    };
+    Some(rest.into_iter().flat_map(|rest| {
+        rest.split('/').map(|token| {
+            if token.contains('~') {
+                Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+            } else {
+                Cow::Borrowed(token)
+            }
+        })
+    }))
+}
+
+// Parses an array index token. Leading zeros are not permitted.
+fn parse_index(token: &str) -> Option<usize> {
+    if token.starts_with('+') || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+// Follows a sequence of reference tokens without interning them, since a key
+// which is not interned cannot be present in any object.
+fn find<T: AsRef<str>>(value: &IValue, mut tokens: impl Iterator<Item = T>) -> Option<&IValue> {
+    tokens.try_fold(value, |target, token| match target.destructure_ref() {
+        DestructuredRef::Object(obj) => obj.get_str(token.as_ref()),
+        DestructuredRef::Array(arr) => arr.get(parse_index(token.as_ref())?),
+        _ => None,
+    })
+}
+
+// The remaining tokens of each pointer passed to `IValue::pointer_many_mut`,
+// along with the position of its result.
+type Targets<'p> = Vec<(usize, &'p [Cow<'p, str>])>;
+
+// Resolves each target to a distinct value within `value`. Values are reached
+// by walking down the tree once, splitting each array or object into disjoint
+// items, so that the mutable references handed out never overlap.
+fn resolve_many_mut<'a>(
+    value: &'a mut IValue,
+    targets: Targets<'_>,
+    out: &mut [Option<&'a mut IValue>],
+) {
+    // Overlapping pointers were rejected up front, so a pointer which ends
+    // here must be the only one left.
+    if let [(i, [])] = targets[..] {
+        out[i] = Some(value);
+        return;
+    }
+    // Group the targets by the position of the item their next token refers to
+    let mut children: BTreeMap<usize, Targets<'_>> = BTreeMap::new();
+    match value.destructure_mut() {
+        DestructuredMut::Array(arr) => {
+            for (i, tokens) in targets {
+                if let Some(index) = parse_index(&tokens[0]) {
+                    children.entry(index).or_default().push((i, &tokens[1..]));
+                }
+            }
+            resolve_children(arr.as_mut_slice().iter_mut(), |item| item, children, out);
+        }
+        DestructuredMut::Object(obj) => {
+            for (i, tokens) in targets {
+                if let Some(index) = obj.position_of(&tokens[0]) {
+                    children.entry(index).or_default().push((i, &tokens[1..]));
+                }
+            }
+            resolve_children(obj.iter_mut(), |(_, item)| item, children, out);
+        }
+        _ => {}
+    }
+}
+
+// Resolves the targets grouped under each item. `project` gets the value out of
+// an item, and is separate from `items` so that skipping items stays cheap.
+fn resolve_children<'a, I: Iterator>(
+    mut items: I,
+    project: impl Fn(I::Item) -> &'a mut IValue,
+    children: BTreeMap<usize, Targets<'_>>,
+    out: &mut [Option<&'a mut IValue>],
+) {
+    let mut next = 0;
+    for (index, targets) in children {
+        match items.nth(index - next) {
+            Some(item) => resolve_many_mut(project(item), targets, out),
+            None => return,
+        }
+        next = index + 1;
+    }
+}
+
+// Appends a single reference token to a JSON Pointer, escaping as necessary.
+pub(crate) fn push_token(pointer: &mut String, token: &str) {
+    pointer.push('/');
+    for c in token.chars() {
+        match c {
+            '~' => pointer.push_str("~0"),
+            '/' => pointer.push_str("~1"),
+            c => pointer.push(c),
+        }
+    }
+}
+
+// Appends an array index to a JSON Pointer.
+pub(crate) fn push_index(pointer: &mut String, index: usize) {
+    // Writing to a `String` cannot fail
+    let _ = write!(pointer, "/{}", index);
+}
+
+impl IValue {
+    /// Looks up a value by [JSON Pointer](https://tools.ietf.org/html/rfc6901).
+    ///
+    /// The empty string refers to this value itself. Each `/`-separated token
+    /// is used as a key into objects, or as a (decimal) index into arrays,
+    /// with `~0` and `~1` unescaped to `~` and `/` respectively.
+    ///
+    /// Returns `None` if the pointer is malformed or does not refer to an
+    /// existing value.
+    #[must_use]
+    pub fn pointer(&self, pointer: &str) -> Option<&IValue> {
+        tokens(pointer)?.try_fold(self, |target, token| match target.destructure_ref() {
+            DestructuredRef::Object(obj) => obj.get(&*token),
+            DestructuredRef::Array(arr) => arr.get(parse_index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// Looks up a value by [JSON Pointer](https://tools.ietf.org/html/rfc6901)
+    /// and returns a mutable reference to it.
+    ///
+    /// See [`IValue::pointer`] for the syntax accepted.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut IValue> {
+        tokens(pointer)?.try_fold(self, |target, token| match target.destructure_mut() {
+            DestructuredMut::Object(obj) => obj.get_mut(&*token),
+            DestructuredMut::Array(arr) => arr.get_mut(parse_index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// Looks up several values by [JSON Pointer](https://tools.ietf.org/html/rfc6901)
+    /// at once, returning a mutable reference to each value found.
+    ///
+    /// See [`IValue::pointer`] for the syntax accepted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the same value could be reached more than once, ie. if any
+    /// pointer is equal to, or a prefix of, another pointer.
+    pub fn pointer_many_mut(&mut self, pointers: &[&str]) -> Vec<Option<&mut IValue>> {
+        let paths: Vec<Option<Vec<Cow<'_, str>>>> = pointers
+            .iter()
+            .map(|pointer| Some(tokens(pointer)?.collect()))
+            .collect();
+        for (i, a) in paths.iter().enumerate() {
+            for (j, b) in paths.iter().enumerate().skip(i + 1) {
+                if let (Some(a), Some(b)) = (a, b) {
+                    assert!(
+                        !a.iter().zip(b).all(|(x, y)| x == y),
+                        "overlapping pointers {:?} and {:?}",
+                        pointers[i],
+                        pointers[j]
+                    );
+                }
+            }
+        }
+        let targets = paths
+            .iter()
+            .enumerate()
+            .filter_map(|(i, path)| Some((i, path.as_deref()?)))
+            .collect();
+        let mut res: Vec<_> = pointers.iter().map(|_| None).collect();
+        resolve_many_mut(self, targets, &mut res);
+        res
+    }
+
+    /// Returns `true` if the [JSON Pointer](https://tools.ietf.org/html/rfc6901)
+    /// refers to an existing value.
+    ///
+    /// Unlike [`IValue::pointer`], this never interns the keys it looks up.
+    #[must_use]
+    pub fn pointer_exists(&self, pointer: &str) -> bool {
+        tokens(pointer)
+            .and_then(|tokens| find(self, tokens))
+            .is_some()
+    }
+
+    /// Returns `true` if the `.`-separated path refers to an existing value.
+    ///
+    /// Each segment of the path is used as a key into objects, or as a
+    /// (decimal) index into arrays, so `a.0.b` is equivalent to the pointer
+    /// `/a/0/b`. There is no escaping, so keys containing a `.` cannot be
+    /// reached. The empty path refers to this value itself.
+    #[must_use]
+    pub fn path_exists(&self, dotted: &str) -> bool {
+        dotted.is_empty() || find(self, dotted.split('.')).is_some()
+    }
+
+    /// Compares two values by the values found at the same
+    /// [JSON Pointer](https://tools.ietf.org/html/rfc6901) within each, for
+    /// use with `sort_by`.
+    ///
+    /// A missing value sorts before any other value. Values of different
+    /// types are ordered by their [`ValueType`](crate::ValueType), so all
+    /// numbers sort before all strings, and objects compare as equal to each
+    /// other.
+    #[must_use]
+    pub fn cmp_by_pointer(a: &IValue, b: &IValue, pointer: &str) -> Ordering {
+        match (a.pointer(pointer), b.pointer(pointer)) {
+            (Some(a), Some(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        }
+    }
+
+    /// Returns a copy of this value containing only the values referred to by
+    /// each [JSON Pointer](https://tools.ietf.org/html/rfc6901), along with the
+    /// objects and arrays needed to reach them.
+    ///
+    /// Each pointer refers to the same value in the result as it does in this
+    /// value, so arrays are padded with `null`s before any projected items.
+    /// Pointers which are malformed or do not refer to an existing value are
+    /// ignored, and if none match the result is `null`.
+    ///
+    /// See [`IValue::pointer`] for the syntax accepted.
+    #[must_use]
+    pub fn project(&self, pointers: &[&str]) -> IValue {
+        let mut res = IValue::NULL;
+        for pointer in pointers {
+            let source = match self.pointer(pointer) {
+                Some(source) => source,
+                None => continue,
+            };
+            let mut src = self;
+            let mut dest = &mut res;
+            // The pointer is known to be valid
+            for token in tokens(pointer).unwrap() {
+                if let Some(obj) = src.as_object() {
+                    src = &obj[&*token];
+                    dest = dest.get_mut_or_null(&*token);
+                } else {
+                    let index = parse_index(&token).unwrap();
+                    src = &src[index];
+                    dest = dest.get_mut_or_null(index);
+                }
+            }
+            *dest = source.clone();
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IArray, IString};
+
+    #[mockalloc::test]
+    fn can_look_up_pointer() {
+        let mut x = ijson!({
+            "foo": ["bar", "baz"],
+            "": 0,
+            "a/b": 1,
+            "m~n": 2,
+            "nested": {"list": [{"x": true}]}
+        });
+
+        assert_eq!(x.pointer(""), Some(&x));
+        assert_eq!(x.pointer("/foo"), Some(&ijson!(["bar", "baz"])));
+        assert_eq!(x.pointer("/foo/0"), Some(&ijson!("bar")));
+        assert_eq!(x.pointer("/"), Some(&ijson!(0)));
+        assert_eq!(x.pointer("/a~1b"), Some(&ijson!(1)));
+        assert_eq!(x.pointer("/m~0n"), Some(&ijson!(2)));
+        assert_eq!(x.pointer("/nested/list/0/x"), Some(&ijson!(true)));
+
+        assert_eq!(x.pointer("foo"), None);
+        assert_eq!(x.pointer("/foo/2"), None);
+        assert_eq!(x.pointer("/foo/01"), None);
+        assert_eq!(x.pointer("/foo/-"), None);
+        assert_eq!(x.pointer("/nested/list/0/x/y"), None);
+
+        *x.pointer_mut("/foo/1").unwrap() = ijson!("qux");
+        assert_eq!(x.pointer("/foo"), Some(&ijson!(["bar", "qux"])));
+    }
+
+    #[mockalloc::test]
+    fn can_project() {
+        let x = ijson!({
+            "a": {"b": [1, 2], "c": true},
+            "c": ["d", {"e": null}],
+            "f": "g"
+        });
+
+        assert_eq!(
+            x.project(&["/a/b", "/c/1/e", "/missing", "/a/b/5", "bad"]),
+            ijson!({"a": {"b": [1, 2]}, "c": [null, {"e": null}]})
+        );
+        assert_eq!(
+            x.project(&["/c/0", "/c"]),
+            ijson!({"c": ["d", {"e": null}]})
+        );
+        assert_eq!(x.project(&[""]), x);
+        assert_eq!(x.project(&[]), IValue::NULL);
+    }
+
+    #[test]
+    fn can_build_pointer() {
+        let mut pointer = String::new();
+        push_token(&mut pointer, "a/b");
+        push_index(&mut pointer, 3);
+        push_token(&mut pointer, "m~n");
+        assert_eq!(pointer, "/a~1b/3/m~0n");
+    }
+
+    #[mockalloc::test]
+    fn can_sort_by_pointer() {
+        let mut x: IArray = vec![
+            ijson!({"name": "a", "info": {"age": 30}}),
+            ijson!({"name": "b", "info": {"age": 20.5}}),
+            ijson!({"name": "c"}),
+            ijson!({"name": "d", "info": {"age": 25}}),
+        ]
+        .into();
+        x.sort_by(|a, b| IValue::cmp_by_pointer(a, b, "/info/age"));
+
+        let names: Vec<_> = x.iter().map(|v| v["name"].str_or("")).collect();
+        assert_eq!(names, ["c", "b", "d", "a"]);
+    }
+
+    #[mockalloc::test]
+    fn can_check_existence() {
+        let x = ijson!({"a": [1, {"b/c": null}], "d.e": true});
+
+        assert!(x.pointer_exists(""));
+        assert!(x.pointer_exists("/a/1/b~1c"));
+        assert!(!x.pointer_exists("/a/2"));
+        assert!(!x.pointer_exists("/a/01"));
+        assert!(!x.pointer_exists("/not-interned-pointer-key"));
+        assert!(IString::lookup("not-interned-pointer-key").is_none());
+        assert!(!x.pointer_exists("a"));
+
+        assert!(x.path_exists(""));
+        assert!(x.path_exists("a.1.b/c"));
+        assert!(x.path_exists("a.0"));
+        assert!(!x.path_exists("a.0.b"));
+        assert!(!x.path_exists("d.e"));
+        assert!(!x.path_exists("not-interned-path-key"));
+    }
+
+    #[mockalloc::test]
+    fn can_look_up_many_pointers() {
+        let mut x = ijson!({"a": {"b": [1, 2], "c": 3}, "d": [{"e": 4}]});
+        #[cfg(feature = "snapshot")]
+        let snapshot = x.snapshot();
+        let mut targets = x.pointer_many_mut(&["/a/b/1", "/a/c", "/d/0/e", "/missing", "bad"]);
+        assert!(targets[3].is_none());
+        assert!(targets[4].is_none());
+        for (i, target) in targets.iter_mut().take(3).enumerate() {
+            **target.as_mut().unwrap() = IValue::from(i * 10);
+        }
+
+        assert_eq!(x, ijson!({"a": {"b": [1, 0], "c": 10}, "d": [{"e": 20}]}));
+        #[cfg(feature = "snapshot")]
+        assert_eq!(
+            snapshot,
+            ijson!({"a": {"b": [1, 2], "c": 3}, "d": [{"e": 4}]})
+        );
+    }
+
+    #[mockalloc::test]
+    fn can_look_up_many_sibling_pointers() {
+        let mut x = ijson!({"a": [1, 2, 3], "o": {"p": 4, "q": 5, "r": 6}});
+        let mut targets =
+            x.pointer_many_mut(&["/a/2", "/o/q", "/a/0", "/o/p", "/a/3", "/o/z", "/a/x"]);
+        assert!(targets[4].is_none());
+        assert!(targets[5].is_none());
+        assert!(targets[6].is_none());
+        for (i, target) in targets.iter_mut().take(4).enumerate() {
+            **target.as_mut().unwrap() = IValue::from(i * 10);
+        }
+
+        assert_eq!(
+            x,
+            ijson!({"a": [20, 2, 0], "o": {"p": 30, "q": 10, "r": 6}})
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "overlapping pointers \"/a/b/0\" and \"/a/b\"")]
+    fn pointer_many_mut_rejects_overlap() {
+        let mut x = ijson!({"a": {"b": [1]}});
+        x.pointer_many_mut(&["/a/c", "/a/b/0", "/a/b"]);
+    }
+}
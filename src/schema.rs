@@ -0,0 +1,320 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use super::array::IArray;
+use super::number::INumber;
+use super::object::IObject;
+use super::pointer::{push_index, push_token};
+use super::value::{DestructuredRef, IValue};
+
+/// A single failure reported by [`IValue::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pointer: String,
+    keyword: &'static str,
+    message: String,
+}
+
+impl ValidationError {
+    /// The [JSON Pointer](https://tools.ietf.org/html/rfc6901) to the value
+    /// which failed validation.
+    #[must_use]
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+    /// The schema keyword which was not satisfied, eg. `"required"`.
+    #[must_use]
+    pub fn keyword(&self) -> &str {
+        self.keyword
+    }
+    /// A human readable description of the failure.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at \"{}\"", self.message, self.pointer)
+    }
+}
+
+impl Error for ValidationError {}
+
+fn type_matches(value: &IValue, name: &str) -> bool {
+    match (value.destructure_ref(), name) {
+        (DestructuredRef::Null, "null")
+        | (DestructuredRef::Bool(_), "boolean")
+        | (DestructuredRef::Number(_), "number")
+        | (DestructuredRef::String(_), "string")
+        | (DestructuredRef::Array(_), "array")
+        | (DestructuredRef::Object(_), "object") => true,
+        (DestructuredRef::Number(n), "integer") => {
+            !n.has_decimal_point() || n.to_f64_lossy().fract() == 0.0
+        }
+        _ => false,
+    }
+}
+
+struct Validator {
+    pointer: String,
+    errors: Vec<ValidationError>,
+}
+
+impl Validator {
+    fn error(&mut self, keyword: &'static str, message: String) {
+        self.errors.push(ValidationError {
+            pointer: self.pointer.clone(),
+            keyword,
+            message,
+        });
+    }
+
+    fn with_token(&mut self, token: &str, f: impl FnOnce(&mut Self)) {
+        let len = self.pointer.len();
+        push_token(&mut self.pointer, token);
+        f(self);
+        self.pointer.truncate(len);
+    }
+
+    fn with_index(&mut self, index: usize, f: impl FnOnce(&mut Self)) {
+        let len = self.pointer.len();
+        push_index(&mut self.pointer, index);
+        f(self);
+        self.pointer.truncate(len);
+    }
+
+    fn validate(&mut self, value: &IValue, schema: &IValue) {
+        let schema = match schema.destructure_ref() {
+            DestructuredRef::Object(schema) => schema,
+            DestructuredRef::Bool(false) => {
+                return self.error("false", "no value is permitted".into());
+            }
+            _ => return,
+        };
+
+        if let Some(expected) = schema.get("type") {
+            self.validate_type(value, expected);
+        }
+        if let Some(options) = schema.get("enum").and_then(IValue::as_array) {
+            if !options.contains(value) {
+                self.error("enum", format!("{:?} is not one of {:?}", value, options));
+            }
+        }
+        match value.destructure_ref() {
+            DestructuredRef::Number(n) => self.validate_number(n, schema),
+            DestructuredRef::String(s) => {
+                let len = s.as_str().chars().count();
+                if let Some(min) = schema.get("minLength").and_then(IValue::to_usize) {
+                    if len < min {
+                        self.error(
+                            "minLength",
+                            format!("string of length {} is shorter than {}", len, min),
+                        );
+                    }
+                }
+                if let Some(max) = schema.get("maxLength").and_then(IValue::to_usize) {
+                    if len > max {
+                        self.error(
+                            "maxLength",
+                            format!("string of length {} is longer than {}", len, max),
+                        );
+                    }
+                }
+            }
+            DestructuredRef::Array(arr) => self.validate_array(arr, schema),
+            DestructuredRef::Object(obj) => self.validate_object(obj, schema),
+            _ => {}
+        }
+    }
+
+    fn validate_type(&mut self, value: &IValue, expected: &IValue) {
+        let matches = match expected.destructure_ref() {
+            DestructuredRef::String(name) => type_matches(value, name),
+            DestructuredRef::Array(names) => names
+                .iter()
+                .filter_map(IValue::as_string)
+                .any(|name| type_matches(value, name)),
+            _ => true,
+        };
+        if !matches {
+            self.error(
+                "type",
+                format!("expected type {:?}, found {:?}", expected, value.type_()),
+            );
+        }
+    }
+
+    fn validate_number(&mut self, n: &INumber, schema: &IObject) {
+        if let Some(min) = schema.get("minimum").and_then(IValue::as_number) {
+            if n < min {
+                self.error("minimum", format!("{:?} is less than {:?}", n, min));
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(IValue::as_number) {
+            if n > max {
+                self.error("maximum", format!("{:?} is greater than {:?}", n, max));
+            }
+        }
+    }
+
+    fn validate_array(&mut self, arr: &IArray, schema: &IObject) {
+        match schema.get("items").map(IValue::destructure_ref) {
+            // A list of schemas validates each item positionally
+            Some(DestructuredRef::Array(items)) => {
+                for (index, (item, item_schema)) in arr.iter().zip(items.iter()).enumerate() {
+                    self.with_index(index, |v| v.validate(item, item_schema));
+                }
+            }
+            Some(_) => {
+                let item_schema = &schema["items"];
+                for (index, item) in arr.iter().enumerate() {
+                    self.with_index(index, |v| v.validate(item, item_schema));
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn validate_object(&mut self, obj: &IObject, schema: &IObject) {
+        if let Some(required) = schema.get("required").and_then(IValue::as_array) {
+            for key in required.iter().filter_map(IValue::as_string) {
+                if !obj.contains_key(key) {
+                    self.error("required", format!("missing required property {:?}", key));
+                }
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(IValue::as_object) {
+            for (key, property_schema) in properties {
+                if let Some(property) = obj.get(key) {
+                    self.with_token(key, |v| v.validate(property, property_schema));
+                }
+            }
+        }
+    }
+}
+
+impl IValue {
+    /// Validates this value against a JSON Schema.
+    ///
+    /// Only a pragmatic subset of the specification is supported: the
+    /// `type`, `enum`, `minimum`, `maximum`, `minLength`, `maxLength`,
+    /// `items`, `required` and `properties` keywords. Other keywords are
+    /// ignored, as are `$ref`s. The boolean schemas `true` and `false` are
+    /// also understood.
+    ///
+    /// On failure, every violation found is returned, each one identifying
+    /// the offending value by JSON Pointer.
+    pub fn validate(&self, schema: &IValue) -> Result<(), Vec<ValidationError>> {
+        let mut validator = Validator {
+            pointer: String::new(),
+            errors: Vec::new(),
+        };
+        validator.validate(self, schema);
+        if validator.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(validator.errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_schema() -> IValue {
+        ijson!({
+            "type": "object",
+            "required": ["name", "port"],
+            "properties": {
+                "name": {"type": "string", "minLength": 1, "maxLength": 8},
+                "port": {"type": "integer", "minimum": 1, "maximum": 65535},
+                "mode": {"enum": ["fast", "safe"]},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "limits": {
+                    "type": ["object", "null"],
+                    "properties": {
+                        "a/b": {"type": "number", "maximum": 1.5}
+                    }
+                }
+            }
+        })
+    }
+
+    fn pointers(value: IValue) -> Vec<(String, &'static str)> {
+        value
+            .validate(&config_schema())
+            .unwrap_err()
+            .into_iter()
+            .map(|e| (e.pointer, e.keyword))
+            .collect()
+    }
+
+    #[mockalloc::test]
+    fn can_validate_passing() {
+        let x = ijson!({
+            "name": "server",
+            "port": 8080,
+            "mode": "safe",
+            "tags": ["a", "b"],
+            "limits": {"a/b": 1.0},
+            "extra": true
+        });
+        assert_eq!(x.validate(&config_schema()), Ok(()));
+        assert_eq!(x.validate(&ijson!(true)), Ok(()));
+        assert!(x.validate(&ijson!(false)).is_err());
+    }
+
+    #[mockalloc::test]
+    fn can_validate_failing() {
+        assert_eq!(pointers(ijson!([])), vec![(String::new(), "type")]);
+        assert_eq!(
+            pointers(ijson!({"name": "server"})),
+            vec![(String::new(), "required")]
+        );
+        assert_eq!(
+            pointers(ijson!({"name": "", "port": 0.5})),
+            vec![
+                ("/name".into(), "minLength"),
+                ("/port".into(), "type"),
+                ("/port".into(), "minimum"),
+            ]
+        );
+        assert_eq!(
+            pointers(ijson!({"name": "much too long", "port": 70000, "mode": "slow"})),
+            vec![
+                ("/name".into(), "maxLength"),
+                ("/port".into(), "maximum"),
+                ("/mode".into(), "enum"),
+            ]
+        );
+        assert_eq!(
+            pointers(ijson!({
+                "name": "x",
+                "port": 80,
+                "tags": ["a", 1, "c", null],
+                "limits": {"a/b": 2}
+            })),
+            vec![
+                ("/tags/1".into(), "type"),
+                ("/tags/3".into(), "type"),
+                ("/limits/a~1b".into(), "maximum"),
+            ]
+        );
+    }
+
+    #[mockalloc::test]
+    fn can_validate_positional_items() {
+        let schema = ijson!({"items": [{"type": "string"}, {"type": "number"}]});
+        assert_eq!(ijson!(["a", 1, null]).validate(&schema), Ok(()));
+        let errors = ijson!([1, "a"]).validate(&schema).unwrap_err();
+        let found: Vec<_> = errors.iter().map(ValidationError::pointer).collect();
+        assert_eq!(found, vec!["/0", "/1"]);
+        assert_eq!(
+            errors[0].to_string(),
+            "expected type \"string\", found Number at \"/0\""
+        );
+    }
+}
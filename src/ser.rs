@@ -1,16 +1,42 @@
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
 use serde::ser::{
     Error as _, Impossible, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
     SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
 };
 use serde::{Serialize, Serializer};
 use serde_json::error::Error;
+use serde_json::ser::{CompactFormatter, Formatter};
 
 use super::array::IArray;
 use super::number::INumber;
+#[cfg(feature = "arbitrary_precision")]
+use super::number::NUMBER_TOKEN;
 use super::object::IObject;
 use super::string::IString;
 use super::value::{DestructuredRef, IValue};
 
+impl IValue {
+    /// Appends the JSON encoding of this value to the end of `buf`.
+    ///
+    /// Unlike [`serde_json::to_vec`], this writes into an existing buffer, so
+    /// when encoding many values the buffer can be cleared and reused to avoid
+    /// allocating a new one each time.
+    pub fn append_to_vec(&self, buf: &mut Vec<u8>) {
+        serde_json::to_writer(buf, self).expect("writing an `IValue` to a `Vec` cannot fail");
+    }
+
+    /// Converts this value into its JSON encoding.
+    ///
+    /// Unlike [`serde_json::to_string`], this cannot fail, and strings are
+    /// encoded with their quotes, so the result is always valid JSON.
+    #[must_use]
+    pub fn into_json_string(self) -> String {
+        serde_json::to_string(&self).expect("serializing an `IValue` cannot fail")
+    }
+}
+
 impl Serialize for IValue {
     #[inline]
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -33,6 +59,11 @@ impl Serialize for INumber {
     where
         S: Serializer,
     {
+        #[cfg(feature = "arbitrary_precision")]
+        if let Some(text) = self.source_text() {
+            let number: serde_json::Number = text.parse().map_err(S::Error::custom)?;
+            return number.serialize(serializer);
+        }
         if self.has_decimal_point() {
             serializer.serialize_f64(self.to_f64().unwrap())
         } else if let Some(v) = self.to_i64() {
@@ -158,8 +189,7 @@ impl Serializer for ValueSerializer {
     }
 
     fn serialize_bytes(self, value: &[u8]) -> Result<IValue, Self::Error> {
-        let array: IArray = value.iter().copied().collect();
-        Ok(array.into())
+        Ok(IValue::from_bytes(value))
     }
 
     #[inline]
@@ -593,6 +623,16 @@ impl SerializeStruct for SerializeObject {
     }
 
     fn end(self) -> Result<IValue, Self::Error> {
+        // `serde_json::Number` serializes itself as a struct containing its
+        // text when the `arbitrary_precision` feature is enabled.
+        #[cfg(feature = "arbitrary_precision")]
+        if self.object.len() == 1 {
+            if let Some(text) = self.object.get(NUMBER_TOKEN).and_then(IValue::as_string) {
+                return INumber::from_json_text(text)
+                    .map(Into::into)
+                    .ok_or_else(|| Error::custom("invalid number"));
+            }
+        }
         SerializeMap::end(self)
     }
 }
@@ -627,3 +667,467 @@ where
 {
     value.serialize(ValueSerializer)
 }
+
+// Serializes a value with integral floats written out in full.
+struct PlainNumbers<'a>(&'a IValue);
+
+// `serde_json` switches to exponent notation from this magnitude onwards.
+const PLAIN_MIN: f64 = 1e16;
+// Every integral float below this magnitude fits in an `i128`.
+const PLAIN_MAX: f64 = 170_141_183_460_469_231_731_687_303_715_884_105_728.0;
+
+impl Serialize for PlainNumbers<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0.destructure_ref() {
+            DestructuredRef::Number(n) => {
+                if let Some(v) = n.to_f64().filter(|_| n.has_decimal_point()) {
+                    if v.fract() == 0.0 && v.abs() >= PLAIN_MIN && v.abs() < PLAIN_MAX {
+                        return serializer.serialize_i128(v as i128);
+                    }
+                }
+                n.serialize(serializer)
+            }
+            DestructuredRef::Array(arr) => serializer.collect_seq(arr.iter().map(PlainNumbers)),
+            DestructuredRef::Object(obj) => {
+                serializer.collect_map(obj.iter().map(|(k, v)| (k, PlainNumbers(v))))
+            }
+            _ => self.0.serialize(serializer),
+        }
+    }
+}
+
+/// Converts an [`IValue`] to a JSON string, writing large integral floating
+/// point numbers such as `1e21` out in full (`1000000000000000000000`) rather
+/// than in exponent notation.
+///
+/// This only affects numbers with a magnitude of at least `1e16` (below which
+/// `serde_json` does not use exponent notation) and less than `2^127`, which
+/// are written in exponent notation otherwise.
+///
+/// Note that such numbers are stored as an `f64`, so the digits written are
+/// those of the nearest `f64` rather than of the original input, eg. `1e23`
+/// is written as `99999999999999991611392`. Parsing the output gives back the
+/// same `f64`, but the text may differ from what was originally parsed.
+#[must_use]
+pub fn to_string_plain_numbers(value: &IValue) -> String {
+    serde_json::to_string(&PlainNumbers(value))
+        .expect("writing an `IValue` to a `String` cannot fail")
+}
+
+// Counts the bytes written to it, without storing them.
+struct ByteCount(usize);
+
+impl Write for ByteCount {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn digits(mut v: u64) -> usize {
+    let mut res = 1;
+    while v >= 10 {
+        v /= 10;
+        res += 1;
+    }
+    res
+}
+
+// The length of a string once quoted and escaped in the same way as
+// `serde_json` does.
+fn escaped_len(s: &str) -> usize {
+    2 + s
+        .bytes()
+        .map(|b| match b {
+            b'"' | b'\\' | b'\n' | b'\r' | b'\t' | 0x08 | 0x0c => 2,
+            0..=0x1f => 6,
+            _ => 1,
+        })
+        .sum::<usize>()
+}
+
+fn number_len(n: &INumber) -> usize {
+    // Numbers stored with their text are written out unchanged
+    #[cfg(feature = "arbitrary_precision")]
+    if let Some(text) = n.source_text() {
+        return text.len();
+    }
+    if !n.has_decimal_point() {
+        if let Some(v) = n.to_i64() {
+            return usize::from(v < 0) + digits(v.unsigned_abs());
+        } else if let Some(v) = n.to_u64() {
+            return digits(v);
+        }
+    }
+    // Floats are formatted into a counter, as their length is not easy to
+    // predict.
+    let mut count = ByteCount(0);
+    serde_json::to_writer(&mut count, n).expect("writing a number to a counter cannot fail");
+    count.0
+}
+
+impl IValue {
+    /// Returns the length in bytes of the compact JSON encoding of this value,
+    /// as produced by [`serde_json::to_string`], without allocating.
+    #[must_use]
+    pub fn serialized_len(&self) -> usize {
+        match self.destructure_ref() {
+            DestructuredRef::Null => 4,
+            DestructuredRef::Bool(b) => {
+                if b {
+                    4
+                } else {
+                    5
+                }
+            }
+            DestructuredRef::Number(n) => number_len(n),
+            DestructuredRef::String(s) => escaped_len(s),
+            DestructuredRef::Array(arr) => {
+                // Brackets, plus a comma between each pair of items
+                2 + arr.len().saturating_sub(1)
+                    + arr.iter().map(IValue::serialized_len).sum::<usize>()
+            }
+            DestructuredRef::Object(obj) => {
+                // Braces, plus a colon for each entry and a comma between them
+                2 + obj.len()
+                    + obj.len().saturating_sub(1)
+                    + obj
+                        .iter()
+                        .map(|(k, v)| escaped_len(k) + v.serialized_len())
+                        .sum::<usize>()
+            }
+        }
+    }
+}
+
+/// Controls how numbers are written by [`IValue::to_string_with`].
+///
+/// Each method appends a valid JSON number to `out`. The default
+/// implementations write numbers exactly as `serde_json` does, so only the
+/// methods for the types which need custom formatting have to be implemented.
+pub trait NumberFormatter {
+    /// Formats an integer which fits in an `i64`.
+    fn format_i64(&self, v: i64, out: &mut String) {
+        // Writing to a `String` cannot fail
+        let _ = write!(out, "{}", v);
+    }
+    /// Formats an integer which is too large for an `i64`.
+    fn format_u64(&self, v: u64, out: &mut String) {
+        let _ = write!(out, "{}", v);
+    }
+    /// Formats a number with a decimal point or exponent.
+    fn format_f64(&self, v: f64, out: &mut String) {
+        // Long enough for any finite `f64`
+        let mut buf = [0_u8; 32];
+        let mut cursor = &mut buf[..];
+        CompactFormatter
+            .write_f64(&mut cursor, v)
+            .expect("any finite float fits in the buffer");
+        let len = 32 - cursor.len();
+        out.push_str(std::str::from_utf8(&buf[..len]).expect("floats are written as ASCII"));
+    }
+}
+
+// Appends `s` to `out`, quoted and escaped in the same way as `serde_json`.
+fn write_escaped(s: &str, out: &mut String) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    out.push('"');
+    let mut start = 0;
+    for (i, b) in s.bytes().enumerate() {
+        let escape = match b {
+            b'"' => "\\\"",
+            b'\\' => "\\\\",
+            b'\n' => "\\n",
+            b'\r' => "\\r",
+            b'\t' => "\\t",
+            0x08 => "\\b",
+            0x0c => "\\f",
+            0..=0x1f => "",
+            _ => continue,
+        };
+        out.push_str(&s[start..i]);
+        if escape.is_empty() {
+            out.push_str("\\u00");
+            out.push(HEX[usize::from(b >> 4)] as char);
+            out.push(HEX[usize::from(b & 0xf)] as char);
+        } else {
+            out.push_str(escape);
+        }
+        start = i + 1;
+    }
+    out.push_str(&s[start..]);
+    out.push('"');
+}
+
+fn write_with<F: NumberFormatter + ?Sized>(value: &IValue, f: &F, out: &mut String) {
+    match value.destructure_ref() {
+        DestructuredRef::Null => out.push_str("null"),
+        DestructuredRef::Bool(b) => out.push_str(if b { "true" } else { "false" }),
+        DestructuredRef::Number(n) => write_number(n, f, out),
+        DestructuredRef::String(s) => write_escaped(s, out),
+        DestructuredRef::Array(arr) => {
+            out.push('[');
+            for (i, v) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_with(v, f, out);
+            }
+            out.push(']');
+        }
+        DestructuredRef::Object(obj) => {
+            out.push('{');
+            for (i, (k, v)) in obj.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_escaped(k, out);
+                out.push(':');
+                write_with(v, f, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_number<F: NumberFormatter + ?Sized>(n: &INumber, f: &F, out: &mut String) {
+    #[cfg(feature = "arbitrary_precision")]
+    if let Some(text) = n.source_text() {
+        out.push_str(text);
+        return;
+    }
+    if n.has_decimal_point() {
+        f.format_f64(n.to_f64().unwrap(), out);
+    } else if let Some(v) = n.to_i64() {
+        f.format_i64(v, out);
+    } else {
+        f.format_u64(n.to_u64().unwrap(), out);
+    }
+}
+
+impl IValue {
+    /// Converts this value to a JSON string, using `f` to format every number.
+    ///
+    /// With the `arbitrary_precision` feature, numbers which are stored as
+    /// their original text are written out unchanged instead.
+    #[must_use]
+    pub fn to_string_with<F: NumberFormatter + ?Sized>(&self, f: &F) -> String {
+        let mut out = String::new();
+        write_with(self, f, &mut out);
+        out
+    }
+}
+
+// Serializes a value with the entries of every object ordered by key.
+struct SortedKeys<'a>(&'a IValue);
+
+impl Serialize for SortedKeys<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0.destructure_ref() {
+            DestructuredRef::Array(arr) => serializer.collect_seq(arr.iter().map(SortedKeys)),
+            DestructuredRef::Object(obj) => serializer.collect_map(
+                obj.sorted_key_indices()
+                    .into_iter()
+                    .filter_map(|index| obj.get_index(index))
+                    .map(|(k, v)| (k, SortedKeys(v))),
+            ),
+            _ => self.0.serialize(serializer),
+        }
+    }
+}
+
+impl IValue {
+    /// Converts this value to pretty-printed JSON with the keys of every object
+    /// in sorted order, so that the result does not depend on insertion order.
+    ///
+    /// This is intended for human-readable output such as snapshot tests, and
+    /// makes no guarantees about the exact format of numbers or whitespace.
+    #[must_use]
+    pub fn to_debug_sorted(&self) -> String {
+        serde_json::to_string_pretty(&SortedKeys(self))
+            .expect("writing an `IValue` to a `String` cannot fail")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[mockalloc::test]
+    fn can_append_to_vec() {
+        let x: IValue = ijson!({ "a": [1, 2.5, "b", null] });
+        let mut buf = b"prefix:".to_vec();
+        x.append_to_vec(&mut buf);
+
+        assert_eq!(buf, br#"prefix:{"a":[1,2.5,"b",null]}"#);
+    }
+
+    #[mockalloc::test]
+    fn can_convert_into_json_string() {
+        let x: IValue = ijson!({ "a": [1, "b\"c", null] });
+        assert_eq!(x.into_json_string(), r#"{"a":[1,"b\"c",null]}"#);
+        assert_eq!(ijson!([true, 2.5]).into_json_string(), "[true,2.5]");
+        assert_eq!(ijson!("foo").into_json_string(), r#""foo""#);
+        assert_eq!(ijson!(-3).into_json_string(), "-3");
+        assert_eq!(IValue::NULL.into_json_string(), "null");
+    }
+
+    #[mockalloc::test]
+    fn can_write_plain_numbers() {
+        let x: IValue = ijson!({
+            "a": [1e15, 1e16, -2.1e19, 1e21, 1e23],
+            "b": [1.5e20, 1e40, 0.5, 1, u64::MAX]
+        });
+
+        assert_eq!(
+            to_string_plain_numbers(&x),
+            concat!(
+                r#"{"a":[1000000000000000.0,10000000000000000,-21000000000000000000,"#,
+                r#"1000000000000000000000,99999999999999991611392],"#,
+                r#""b":[150000000000000000000,1e+40,0.5,1,18446744073709551615]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn append_to_vec_reuses_buffer() {
+        let x: IValue = ijson!({ "a": [1, 2.5, "b", null], "c": { "d": true } });
+        let mut buf = Vec::new();
+        x.append_to_vec(&mut buf);
+        let expected = buf.clone();
+
+        let info = mockalloc::record_allocs(|| {
+            for _ in 0..10 {
+                buf.clear();
+                x.append_to_vec(&mut buf);
+            }
+        });
+
+        assert_eq!(info.num_allocs(), 0);
+        assert_eq!(buf, expected);
+    }
+
+    #[mockalloc::test]
+    fn can_format_numbers() {
+        struct TwoDecimals;
+        impl NumberFormatter for TwoDecimals {
+            fn format_f64(&self, v: f64, out: &mut String) {
+                let _ = write!(out, "{:.2}", v);
+            }
+        }
+        struct Defaults;
+        impl NumberFormatter for Defaults {}
+
+        let x: IValue = ijson!({"a\"b": [1.5, 2, -0.126, u64::MAX], "c": "d", "e": [null, true]});
+        assert_eq!(
+            x.to_string_with(&TwoDecimals),
+            r#"{"a\"b":[1.50,2,-0.13,18446744073709551615],"c":"d","e":[null,true]}"#
+        );
+        assert_eq!(
+            x.to_string_with(&Defaults),
+            serde_json::to_string(&x).unwrap()
+        );
+
+        let y: IValue = ijson!({
+            "esc\u{1}aped": "\"\\\n\r\t\u{8}\u{c}\u{1f}\u{7f}é",
+            "floats": [1.0, -0.0, 1e20, 1.5e-7, f64::MAX, f64::MIN_POSITIVE]
+        });
+        assert_eq!(
+            y.to_string_with(&Defaults),
+            serde_json::to_string(&y).unwrap()
+        );
+    }
+
+    #[cfg(feature = "preserve_number_text")]
+    #[mockalloc::test]
+    fn format_numbers_keeps_preserved_text() {
+        struct Defaults;
+        impl NumberFormatter for Defaults {}
+
+        let x: IValue = serde_json::from_str("[-0,1.00,1e+2,100000000000000000000000]").unwrap();
+        assert_eq!(
+            x.to_string_with(&Defaults),
+            "[-0,1.00,1e+2,100000000000000000000000]"
+        );
+    }
+
+    #[test]
+    fn format_numbers_without_allocating_per_item() {
+        struct Defaults;
+        impl NumberFormatter for Defaults {}
+
+        let x: IValue = (0..100)
+            .map(|i| ijson!({ "n": i, "f": (i as f64) / 4.0, "s": "a\"b" }))
+            .collect();
+        let info = mockalloc::record_allocs(|| {
+            let _ = x.to_string_with(&Defaults);
+        });
+
+        // Only the output string itself is allocated, as it grows
+        assert!(info.num_allocs() < 20, "{} allocations", info.num_allocs());
+    }
+
+    #[mockalloc::test]
+    fn can_measure_serialized_len() {
+        let values = [
+            ijson!(null),
+            ijson!(true),
+            ijson!(false),
+            ijson!(0),
+            ijson!(-7),
+            ijson!(i64::MIN),
+            ijson!(u64::MAX),
+            ijson!(0.1),
+            ijson!(-1.5e300),
+            ijson!(1e16),
+            ijson!(""),
+            ijson!("quote\" backslash\\ newline\n tab\t bell\u{7} del\u{7f} é 😀"),
+            ijson!([]),
+            ijson!({}),
+            ijson!([1, [2, "three"], {"four": 4.5}]),
+            ijson!({"a\u{1}b": {"c": [null, true]}, "d": "e"}),
+            #[cfg(feature = "preserve_number_text")]
+            serde_json::from_str("[-0,1.00,1e+2,100000000000000000000000]").unwrap(),
+        ];
+        for v in &values {
+            assert_eq!(
+                v.serialized_len(),
+                serde_json::to_string(v).unwrap().len(),
+                "{:?}",
+                v
+            );
+        }
+    }
+
+    #[mockalloc::test]
+    fn debug_sorted_ignores_insertion_order() {
+        let x: IValue = ijson!({ "b": [1, { "d": null, "c": true }], "a": "x" });
+        let y: IValue = ijson!({ "a": "x", "b": [1, { "c": true, "d": null }] });
+
+        assert_eq!(x.to_debug_sorted(), y.to_debug_sorted());
+        assert_eq!(
+            x.to_debug_sorted(),
+            concat!(
+                "{\n",
+                "  \"a\": \"x\",\n",
+                "  \"b\": [\n",
+                "    1,\n",
+                "    {\n",
+                "      \"c\": true,\n",
+                "      \"d\": null\n",
+                "    }\n",
+                "  ]\n",
+                "}"
+            )
+        );
+    }
+}
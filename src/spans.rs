@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+
+use serde_json::error::Error;
+
+use super::pointer::{push_index, push_token};
+use super::string::init_cache;
+use super::value::IValue;
+
+/// The location of every value within the source text, returned from
+/// [`from_str_with_spans`].
+///
+/// Values are identified by their [JSON Pointer](https://tools.ietf.org/html/rfc6901),
+/// as accepted by [`IValue::pointer`], and each span is the `(start, end)` byte
+/// range of the value's text, excluding surrounding whitespace.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpanMap {
+    spans: HashMap<String, (usize, usize)>,
+}
+
+impl SpanMap {
+    /// Returns the span of the value referred to by `pointer`, or `None` if
+    /// there is no such value.
+    #[must_use]
+    pub fn get(&self, pointer: &str) -> Option<(usize, usize)> {
+        self.spans.get(pointer).copied()
+    }
+
+    /// Returns the number of values with a recorded span.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Returns `true` if no spans were recorded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Returns an iterator over every pointer and its span, in no particular
+    /// order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, (usize, usize))> {
+        self.spans.iter().map(|(k, &v)| (k.as_str(), v))
+    }
+}
+
+// Walks text which is already known to be valid JSON, recording the span of
+// each value as it goes.
+struct Scanner<'a> {
+    text: &'a str,
+    pos: usize,
+    pointer: String,
+    spans: HashMap<String, (usize, usize)>,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> u8 {
+        self.text.as_bytes()[self.pos]
+    }
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.text.len() && self.peek().is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+    // Skips past a string, and returns its raw text including the quotes.
+    fn string(&mut self) -> &str {
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.peek() {
+                b'\\' => self.pos += 2,
+                b'"' => break,
+                _ => self.pos += 1,
+            }
+        }
+        self.pos += 1;
+        &self.text[start..self.pos]
+    }
+    fn value(&mut self) {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            b'{' => {
+                self.pos += 1;
+                self.skip_whitespace();
+                while self.peek() != b'}' {
+                    let raw_key = self.string();
+                    let key = if raw_key.contains('\\') {
+                        // The text is valid, so the key must unescape successfully
+                        serde_json::from_str(raw_key).unwrap()
+                    } else {
+                        raw_key[1..raw_key.len() - 1].to_owned()
+                    };
+                    self.skip_whitespace();
+                    // Skip the ':'
+                    self.pos += 1;
+                    let len = self.pointer.len();
+                    push_token(&mut self.pointer, &key);
+                    if self.spans.contains_key(&self.pointer) {
+                        self.forget_duplicate();
+                    }
+                    self.value();
+                    self.pointer.truncate(len);
+                    self.separator();
+                }
+                self.pos += 1;
+            }
+            b'[' => {
+                self.pos += 1;
+                self.skip_whitespace();
+                let mut index = 0;
+                while self.peek() != b']' {
+                    let len = self.pointer.len();
+                    push_index(&mut self.pointer, index);
+                    self.value();
+                    self.pointer.truncate(len);
+                    self.separator();
+                    index += 1;
+                }
+                self.pos += 1;
+            }
+            b'"' => {
+                self.string();
+            }
+            // Numbers and literals end at the next delimiter
+            _ => {
+                while self.pos < self.text.len()
+                    && !matches!(self.peek(), b',' | b']' | b'}')
+                    && !self.peek().is_ascii_whitespace()
+                {
+                    self.pos += 1;
+                }
+            }
+        }
+        self.spans.insert(self.pointer.clone(), (start, self.pos));
+    }
+    // Later duplicate keys replace earlier ones, as they do when parsing, so
+    // forget the spans of the earlier value and everything within it.
+    fn forget_duplicate(&mut self) {
+        let pointer = &self.pointer;
+        self.spans.retain(|k, _| {
+            !(k.starts_with(pointer.as_str())
+                && matches!(k.as_bytes().get(pointer.len()), None | Some(b'/')))
+        });
+    }
+    // Skips any whitespace and comma following an item, leaving the position
+    // at the next item or the closing bracket.
+    fn separator(&mut self) {
+        self.skip_whitespace();
+        if self.peek() == b',' {
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+    }
+}
+
+/// Parses a value from a string of JSON, along with the location of every
+/// value within it. See [`SpanMap`].
+///
+/// # Errors
+///
+/// Will return `Error` if `s` is not valid JSON.
+pub fn from_str_with_spans(s: &str) -> Result<(IValue, SpanMap), Error> {
+    init_cache();
+    let value = serde_json::from_str(s)?;
+    let mut scanner = Scanner {
+        text: s,
+        pos: 0,
+        pointer: String::new(),
+        spans: HashMap::new(),
+    };
+    scanner.value();
+    Ok((
+        value,
+        SpanMap {
+            spans: scanner.spans,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[mockalloc::test]
+    fn can_record_spans() {
+        let text = r#" {"a": [1, {"b\/c": "x\"y"}], "d" : true, "e": {} } "#;
+        let (value, spans) = from_str_with_spans(text).unwrap();
+        let span_text = |pointer| {
+            let (start, end) = spans.get(pointer).unwrap();
+            &text[start..end]
+        };
+
+        assert_eq!(spans.len(), 7);
+        assert_eq!(span_text(""), &text[1..text.len() - 1]);
+        assert_eq!(span_text("/a"), r#"[1, {"b\/c": "x\"y"}]"#);
+        assert_eq!(span_text("/a/0"), "1");
+        assert_eq!(span_text("/a/1/b~1c"), r#""x\"y""#);
+        assert_eq!(span_text("/d"), "true");
+        assert_eq!(span_text("/e"), "{}");
+        for (pointer, (start, end)) in spans.iter() {
+            let parsed: IValue = serde_json::from_str(&text[start..end]).unwrap();
+            assert_eq!(value.pointer(pointer), Some(&parsed));
+        }
+        assert!(spans.get("/z").is_none());
+    }
+
+    #[mockalloc::test]
+    fn later_duplicate_keys_replace_spans() {
+        let text = r#"{"a": [1, {"b": 2}], "c": {"a": 3}, "a": 5}"#;
+        let (value, spans) = from_str_with_spans(text).unwrap();
+
+        assert_eq!(spans.len(), 4);
+        assert_eq!(spans.get("/a"), Some((41, 42)));
+        assert!(spans.get("/a/0").is_none());
+        assert!(spans.get("/a/1/b").is_none());
+        for (pointer, (start, end)) in spans.iter() {
+            let parsed: IValue = serde_json::from_str(&text[start..end]).unwrap();
+            assert_eq!(value.pointer(pointer), Some(&parsed));
+        }
+    }
+
+    #[mockalloc::test]
+    fn rejects_invalid_json() {
+        assert!(from_str_with_spans("[1,").is_err());
+    }
+}
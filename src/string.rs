@@ -2,11 +2,15 @@
 
 use std::alloc::{alloc, dealloc, Layout, LayoutError};
 use std::borrow::Borrow;
+use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::fmt::{self, Debug, Formatter};
-use std::hash::Hash;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 use std::ptr::{copy_nonoverlapping, NonNull};
+#[cfg(feature = "direct_strings")]
+use std::sync::atomic::fence;
 use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 
 use dashmap::{DashSet, SharedValue};
@@ -57,10 +61,21 @@ trait HeaderMut<'a>: ThinMutExt<'a, Header> {
 impl<'a, T: ThinRefExt<'a, Header>> HeaderRef<'a> for T {}
 impl<'a, T: ThinMutExt<'a, Header>> HeaderMut<'a> for T {}
 
+// The shard index of strings which are not in the string cache, see
+// `IString::direct`. The cache never has this many shards.
+#[cfg(feature = "direct_strings")]
+const DIRECT: usize = u16::MAX as usize;
+
 lazy_static! {
     static ref STRING_CACHE: DashSet<WeakIString> = DashSet::new();
 }
 
+thread_local! {
+    // The most recent string passed to `IString::intern_static` on this thread,
+    // keyed by the address of the `&'static str`.
+    static LAST_STATIC: RefCell<Option<(*const u8, IString)>> = const { RefCell::new(None) };
+}
+
 // Eagerly initialize the string cache during tests or when the
 // `ctor` feature is enabled.
 #[cfg(any(test, feature = "ctor"))]
@@ -105,7 +120,7 @@ impl Borrow<str> for WeakIString {
     }
 }
 impl WeakIString {
-    fn header(&self) -> ThinRef<Header> {
+    fn header(&self) -> ThinRef<'_, Header> {
         // Safety: pointer is always valid
         unsafe { ThinRef::new(self.ptr.as_ptr()) }
     }
@@ -134,10 +149,56 @@ impl WeakIString {
 ///
 /// Given the nature of `IString` it is better to intern a string once and reuse
 /// it, rather than continually convert from `&str` to `IString`.
+///
+/// With the `direct_strings` feature, strings which are unlikely to be
+/// repeated can skip the cache entirely, see `IString::direct`.
 #[repr(transparent)]
 #[derive(Clone)]
 pub struct IString(pub(crate) IValue);
 
+/// The error returned by [`IString::try_intern`] when a string is longer than
+/// the limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooLong {
+    len: usize,
+    max_len: usize,
+}
+
+impl TooLong {
+    pub(crate) fn check(s: &str, max_len: usize) -> Result<(), TooLong> {
+        if s.len() > max_len {
+            Err(TooLong {
+                len: s.len(),
+                max_len,
+            })
+        } else {
+            Ok(())
+        }
+    }
+    /// The length in bytes of the rejected string.
+    #[must_use]
+    pub fn actual_len(&self) -> usize {
+        self.len
+    }
+    /// The maximum length in bytes which was allowed.
+    #[must_use]
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+}
+
+impl Display for TooLong {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "string of length {} exceeds the maximum of {}",
+            self.len, self.max_len
+        )
+    }
+}
+
+impl Error for TooLong {}
+
 value_subtype_impls!(IString, into_string, as_string, as_string_mut);
 
 static EMPTY_HEADER: Header = Header {
@@ -180,20 +241,60 @@ impl IString {
         }
     }
 
+    /// Returns the `IString` for `s` if it is already interned, without adding
+    /// it to the global string cache otherwise.
+    ///
+    /// Since every `IString` is interned, a `None` result means that no
+    /// `IString` (eg. no object key) with these contents currently exists,
+    /// other than any created by `IString::direct`.
+    #[must_use]
+    pub fn lookup(s: &str) -> Option<Self> {
+        if s.is_empty() {
+            return Some(Self::new());
+        }
+        let cache = &*STRING_CACHE;
+        let shard_index = cache.determine_map(s);
+
+        // Safety: `determine_map` should only return valid shard indices
+        let shard = unsafe { cache.shards().get_unchecked(shard_index) };
+        // Holding the lock prevents the string from being freed before we
+        // increment its reference count.
+        let guard = shard.read();
+        guard.get_key_value(s).map(|(k, _)| k.upgrade())
+    }
+
     /// Converts a `&str` to an `IString` by interning it in the global string cache.
     #[must_use]
     pub fn intern(s: &str) -> Self {
+        Self::intern_tracked(s).0
+    }
+
+    /// Converts a `&str` to an `IString` like [`IString::intern`], additionally
+    /// returning `true` if the string was not already in the cache and so had
+    /// to be allocated.
+    ///
+    /// The empty string is never allocated, so always returns `false`.
+    #[must_use]
+    pub fn intern_tracked(s: &str) -> (Self, bool) {
         if s.is_empty() {
-            return Self::new();
+            return (Self::new(), false);
         }
         let cache = &*STRING_CACHE;
         let shard_index = cache.determine_map(s);
 
         // Safety: `determine_map` should only return valid shard indices
         let shard = unsafe { cache.shards().get_unchecked(shard_index) };
+
+        // Most strings are already interned, and many threads can hold the read
+        // lock at once, so only take the write lock if the string is missing.
+        if let Some((k, _)) = shard.read().get_key_value(s) {
+            return (k.upgrade(), false);
+        }
+
         let mut guard = shard.write();
+        // Another thread may have interned the string since we checked.
         if let Some((k, _)) = guard.get_key_value(s) {
-            k.upgrade()
+            (k.upgrade(), false)
         } else {
             let k = unsafe {
                 WeakIString {
@@ -202,11 +303,116 @@ impl IString {
             };
             let res = k.upgrade();
             guard.insert(k, SharedValue::new(()));
-            res
+            (res, true)
         }
     }
 
-    fn header(&self) -> ThinRef<Header> {
+    /// Converts a `char` to an `IString`, without allocating unless the
+    /// string is not already interned.
+    #[must_use]
+    pub fn from_char(c: char) -> Self {
+        Self::intern(c.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Converts a sequence of `char`s to an `IString`.
+    ///
+    /// The characters must still be collected into a temporary buffer in
+    /// order to look them up in the string cache, but this is sized up-front
+    /// using the iterator's lower size bound.
+    #[must_use]
+    pub fn from_chars(chars: impl IntoIterator<Item = char>) -> Self {
+        let chars = chars.into_iter();
+        let mut buffer = String::with_capacity(chars.size_hint().0);
+        buffer.extend(chars);
+        Self::intern(&buffer)
+    }
+
+    /// Converts a `&str` to an `IString` like [`IString::intern`], unless it is
+    /// longer than `max_len` bytes.
+    ///
+    /// Interned strings stay in the cache for as long as they are referenced, so
+    /// this can be used to avoid interning arbitrarily large untrusted input.
+    ///
+    /// # Errors
+    ///
+    /// Will return `TooLong` without touching the cache if `s` has more than
+    /// `max_len` bytes.
+    pub fn try_intern(s: &str, max_len: usize) -> Result<Self, TooLong> {
+        TooLong::check(s, max_len)?;
+        Ok(Self::intern(s))
+    }
+
+    /// Converts a `&str` to an `IString` without interning it, so that the
+    /// global string cache is never touched.
+    ///
+    /// This is cheaper than [`IString::intern`] for strings which are unlikely
+    /// to be repeated, such as the values in a document which is parsed once
+    /// and then dropped, but every call allocates a new copy of the string.
+    /// Such strings compare equal to interned strings with the same contents,
+    /// but comparing them means comparing their contents rather than a
+    /// pointer. They are interned when they are used as an object key.
+    ///
+    /// Requires the `direct_strings` feature.
+    #[cfg(feature = "direct_strings")]
+    #[must_use]
+    pub fn direct(s: &str) -> Self {
+        if s.is_empty() {
+            return Self::new();
+        }
+        let ptr = Self::alloc(s, DIRECT);
+        unsafe {
+            (*ptr).rc.store(1, AtomicOrdering::Relaxed);
+            IString(IValue::new_ptr(ptr.cast::<u8>(), TypeTag::StringOrNull))
+        }
+    }
+
+    /// Returns `true` unless this string was created by [`IString::direct`].
+    #[must_use]
+    pub fn is_interned(&self) -> bool {
+        #[cfg(feature = "direct_strings")]
+        return self.header().shard_index() != DIRECT;
+        #[cfg(not(feature = "direct_strings"))]
+        true
+    }
+
+    // Object keys must always be interned, as objects hash their keys by
+    // address.
+    pub(crate) fn into_interned(self) -> Self {
+        #[cfg(feature = "direct_strings")]
+        if !self.is_interned() {
+            return Self::intern(&self);
+        }
+        self
+    }
+
+    /// Converts a `&'static str` to an `IString`, skipping the string cache when the
+    /// same literal was the last one interned this way on the current thread.
+    ///
+    /// This is intended for hot loops which repeatedly intern the same constant,
+    /// such as an object key. Only a single entry is remembered per thread, so
+    /// alternating between several literals gains nothing over [`IString::intern`].
+    /// In that case, intern each constant once (eg. using `lazy_static`) and clone
+    /// the resulting `IString` instead.
+    ///
+    /// The remembered string stays alive until it is replaced or the thread exits.
+    #[must_use]
+    pub fn intern_static(s: &'static str) -> Self {
+        LAST_STATIC
+            .try_with(|last| {
+                let mut last = last.borrow_mut();
+                match &*last {
+                    Some((ptr, res)) if *ptr == s.as_ptr() && res.len() == s.len() => res.clone(),
+                    _ => {
+                        let res = Self::intern(s);
+                        *last = Some((s.as_ptr(), res.clone()));
+                        res
+                    }
+                }
+            })
+            .unwrap_or_else(|_| Self::intern(s))
+    }
+
+    fn header(&self) -> ThinRef<'_, Header> {
         unsafe { ThinRef::new(self.0.ptr().cast()) }
     }
 
@@ -234,6 +440,54 @@ impl IString {
         self.header().bytes()
     }
 
+    /// Returns `true` if `prefix` is a prefix of this string.
+    ///
+    /// Since strings are interned, this is a pointer comparison when the two
+    /// strings are equal, and otherwise compares their bytes.
+    #[must_use]
+    pub fn has_prefix(&self, prefix: &IString) -> bool {
+        self == prefix || (prefix.len() < self.len() && self.starts_with(prefix.as_str()))
+    }
+
+    /// Returns a pointer to the UTF-8 bytes of this string, along with their
+    /// length, for passing to foreign code without copying.
+    ///
+    /// The pointer is only valid for as long as this `IString` (or a clone of
+    /// it) is alive, and the bytes must never be written through it. The bytes
+    /// are not nul-terminated.
+    #[must_use]
+    pub fn as_raw_parts(&self) -> (*const u8, usize) {
+        let bytes = self.as_bytes();
+        (bytes.as_ptr(), bytes.len())
+    }
+
+    /// Reconstructs a `&str` from the parts returned by
+    /// [`IString::as_raw_parts`].
+    ///
+    /// # Safety
+    ///
+    /// `ptr` and `len` must have been returned by `as_raw_parts` on an
+    /// `IString` which stays alive for the whole of the lifetime `'a`.
+    #[must_use]
+    pub unsafe fn str_from_raw_parts<'a>(ptr: *const u8, len: usize) -> &'a str {
+        std::str::from_utf8_unchecked(std::slice::from_raw_parts(ptr, len))
+    }
+
+    /// Feeds the contents of this string into `state`, producing the same hash
+    /// as the equivalent `str`.
+    ///
+    /// The [`Hash`] implementation only hashes the address of the interned
+    /// string, which is faster but is only stable for as long as the string
+    /// stays in the cache: once every copy is dropped, interning the same
+    /// text again may produce a different hash. Prefer this method when
+    /// hashes must outlive the strings, or must agree with `str` keys.
+    ///
+    /// With the `direct_strings` feature, [`Hash`] hashes the contents of the
+    /// string as well, but does not necessarily agree with `str`.
+    pub fn content_hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+
     /// Returns the empty string.
     #[must_use]
     pub fn new() -> Self {
@@ -252,6 +506,15 @@ impl IString {
         if !self.is_empty() {
             let hd = self.header();
 
+            #[cfg(feature = "direct_strings")]
+            if !self.is_interned() {
+                if hd.rc.fetch_sub(1, AtomicOrdering::Release) == 1 {
+                    fence(AtomicOrdering::Acquire);
+                    Self::dealloc(unsafe { self.0.ptr().cast() });
+                }
+                return;
+            }
+
             // If the reference count is greater than 1, we can safely decrement it without
             // locking the string cache.
             let mut rc = hd.rc.load(AtomicOrdering::Relaxed);
@@ -342,6 +605,11 @@ impl From<IString> for String {
 
 impl PartialEq for IString {
     fn eq(&self, other: &Self) -> bool {
+        #[cfg(feature = "direct_strings")]
+        if !self.is_interned() || !other.is_interned() {
+            return self.as_str() == other.as_str();
+        }
+        // Interned strings are unique, so are only equal to themselves
         self.0.raw_eq(&other.0)
     }
 }
@@ -370,6 +638,24 @@ impl PartialEq<IString> for String {
     }
 }
 
+impl PartialEq<[u8]> for IString {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.as_bytes() == other
+    }
+}
+
+impl PartialEq<&[u8]> for IString {
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.as_bytes() == *other
+    }
+}
+
+impl PartialEq<IString> for [u8] {
+    fn eq(&self, other: &IString) -> bool {
+        self == other.as_bytes()
+    }
+}
+
 impl Default for IString {
     fn default() -> Self {
         Self::new()
@@ -393,6 +679,11 @@ impl PartialOrd for IString {
 }
 impl Hash for IString {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        // Direct strings must hash the same as the interned copy of their
+        // contents, which has a different address.
+        #[cfg(feature = "direct_strings")]
+        self.as_str().hash(state);
+        #[cfg(not(feature = "direct_strings"))]
         self.0.raw_hash(state);
     }
 }
@@ -407,6 +698,34 @@ impl Debug for IString {
 mod tests {
     use super::*;
 
+    #[cfg(feature = "direct_strings")]
+    #[mockalloc::test]
+    fn can_create_direct_strings() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let hash = |s: &IString| {
+            let mut hasher = DefaultHasher::new();
+            s.hash(&mut hasher);
+            hasher.finish()
+        };
+        let x = IString::direct("not interned");
+        let y = IString::direct("not interned");
+
+        assert!(!x.is_interned());
+        assert_ne!(x.as_ptr(), y.as_ptr());
+        assert_eq!(x, y);
+        assert!(IString::lookup("not interned").is_none());
+
+        let z = IString::intern("not interned");
+        assert!(z.is_interned());
+        assert_eq!(x, z);
+        assert_eq!(z, x);
+        assert_eq!(hash(&x), hash(&z));
+        assert_ne!(x, IString::direct("other"));
+        assert!(IString::direct("").is_interned());
+        assert_eq!(x.clone().into_interned().as_ptr(), z.as_ptr());
+    }
+
     #[mockalloc::test]
     fn can_intern() {
         let x = IString::intern("foo");
@@ -419,6 +738,30 @@ mod tests {
         assert_eq!(y.as_str(), "bar");
     }
 
+    // Not a `mockalloc` test, as the thread-local entry outlives the test body.
+    #[test]
+    fn can_intern_static() {
+        let x = IString::intern_static("foo");
+        let y = IString::intern_static("foo");
+        let z = IString::intern_static("bar");
+
+        assert_eq!(x.as_ptr(), y.as_ptr());
+        assert_eq!(x, IString::intern("foo"));
+        assert_eq!(z, IString::intern("bar"));
+        assert_eq!(IString::intern_static(&"foobar"[..3]), x);
+        assert_eq!(IString::intern_static(""), IString::new());
+
+        // A sub-slice shares the start pointer of the cached string, but
+        // must not be mistaken for it.
+        let full: &'static str = "foobar";
+        let a = IString::intern_static(full);
+        let b = IString::intern_static(&full[..3]);
+        assert_eq!(full.as_ptr(), full[..3].as_ptr());
+        assert_ne!(a, b);
+        assert_eq!(a, IString::intern("foobar"));
+        assert_eq!(b, IString::intern("foo"));
+    }
+
     #[mockalloc::test]
     fn default_interns_string() {
         let x = IString::intern("");
@@ -428,4 +771,135 @@ mod tests {
         assert_eq!(x.as_ptr(), y.as_ptr());
         assert_ne!(x.as_ptr(), z.as_ptr());
     }
+
+    #[mockalloc::test]
+    fn can_intern_with_limit() {
+        let x = IString::try_intern("foo", 3).unwrap();
+        let err = IString::try_intern("food", 3).unwrap_err();
+
+        assert_eq!(x, IString::intern("foo"));
+        assert_eq!(err.actual_len(), 4);
+        assert_eq!(err.max_len(), 3);
+        assert!(IString::lookup("food").is_none());
+    }
+
+    #[mockalloc::test]
+    fn can_hash_content() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let content_hash = |s: &IString| {
+            let mut hasher = DefaultHasher::new();
+            s.content_hash(&mut hasher);
+            hasher.finish()
+        };
+        let str_hash = {
+            let mut hasher = DefaultHasher::new();
+            "foo".hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let x = IString::intern("foo");
+        let before = content_hash(&x);
+        drop(x);
+        // Pad the cache so the string is unlikely to be re-allocated in place
+        let _others: Vec<_> = (0..16).map(|i| IString::intern(&i.to_string())).collect();
+        let y = IString::intern("foo");
+
+        assert_eq!(before, str_hash);
+        assert_eq!(content_hash(&y), str_hash);
+    }
+
+    // Spawns threads, so cannot use `mockalloc::test`
+    #[test]
+    fn can_intern_concurrently() {
+        const THREADS: usize = 8;
+        let names = |t: usize| (0..200).map(move |i| format!("stress-{}", (i * (t + 1)) % 300));
+
+        let results: Vec<Vec<IString>> = (0..THREADS)
+            .map(|t| {
+                std::thread::spawn(move || {
+                    let mut res = Vec::new();
+                    for _ in 0..20 {
+                        res = names(t).map(|s| IString::intern(&s)).collect();
+                    }
+                    res
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+
+        for (t, strings) in results.iter().enumerate() {
+            for (s, name) in strings.iter().zip(names(t)) {
+                assert_eq!(s.as_str(), name);
+                assert_eq!(s.as_ptr(), IString::lookup(&name).unwrap().as_ptr());
+            }
+        }
+        drop(results);
+        for i in 0..300 {
+            assert!(IString::lookup(&format!("stress-{}", i)).is_none());
+        }
+    }
+
+    #[mockalloc::test]
+    fn can_track_interning() {
+        let (x, x_new) = IString::intern_tracked("intern-tracked");
+        let (y, y_new) = IString::intern_tracked("intern-tracked");
+
+        assert!(x_new);
+        assert!(!y_new);
+        assert_eq!(x.as_ptr(), y.as_ptr());
+        assert!(!IString::intern_tracked("").1);
+    }
+
+    #[mockalloc::test]
+    fn can_get_raw_parts() {
+        let x = IString::intern("raw-parts");
+        let (ptr, len) = x.as_raw_parts();
+
+        assert_eq!(len, 9);
+        assert_eq!(ptr, x.as_bytes().as_ptr());
+        assert_eq!(
+            unsafe { IString::str_from_raw_parts(ptr, len) },
+            "raw-parts"
+        );
+        assert_eq!(IString::new().as_raw_parts().1, 0);
+    }
+
+    #[mockalloc::test]
+    fn can_check_prefix() {
+        let x = IString::intern("user.name");
+
+        assert!(x.has_prefix(&x.clone()));
+        assert!(x.has_prefix(&IString::intern("user.")));
+        assert!(x.has_prefix(&IString::new()));
+        assert!(!x.has_prefix(&IString::intern("user.names")));
+        assert!(!x.has_prefix(&IString::intern("name")));
+    }
+
+    #[mockalloc::test]
+    fn can_intern_chars() {
+        let x = IString::from_chars("hello".chars().rev());
+        let y = IString::from_char('é');
+
+        assert_eq!(x, IString::intern("olleh"));
+        assert_eq!(y, IString::intern("é"));
+        assert_eq!(y.len(), 2);
+        assert_eq!(IString::from_chars(None), IString::new());
+    }
+
+    #[mockalloc::test]
+    fn can_compare_with_bytes() {
+        let x = IString::intern("key");
+        let matching: &[u8] = b"key";
+        let other: &[u8] = b"kez";
+
+        assert_eq!(x, matching);
+        assert_eq!(x, *matching);
+        assert_eq!(*matching, x);
+        assert_ne!(x, other);
+        assert_ne!(x, &b"\xff"[..]);
+        assert_ne!(IString::new(), matching);
+    }
 }
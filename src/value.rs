@@ -1,9 +1,12 @@
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
-use std::fmt::{self, Debug, Formatter};
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
 use std::hash::Hash;
 use std::hint::unreachable_unchecked;
+use std::iter::FromIterator;
 use std::mem;
 use std::ops::{Deref, Index, IndexMut};
 use std::ptr::NonNull;
@@ -12,7 +15,7 @@ use std::ptr::NonNull;
 use indexmap::IndexMap;
 
 use super::array::IArray;
-use super::number::INumber;
+use super::number::{INumber, NumberClass};
 use super::object::IObject;
 use super::string::IString;
 
@@ -83,7 +86,7 @@ pub enum Destructured {
 impl Destructured {
     /// Convert to the borrowed form of thie enum.
     #[must_use]
-    pub fn as_ref(&self) -> DestructuredRef {
+    pub fn as_ref(&self) -> DestructuredRef<'_> {
         use DestructuredRef::{Array, Bool, Null, Number, Object, String};
         match self {
             Self::Null => Null,
@@ -204,6 +207,75 @@ pub enum ValueType {
     Object,
 }
 
+impl Display for ValueType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Null => "null",
+            Self::Bool => "boolean",
+            Self::Number => "number",
+            Self::String => "string",
+            Self::Array => "array",
+            Self::Object => "object",
+        })
+    }
+}
+
+/// Error returned by the `IValue::try_*` methods when the value is not of the
+/// expected type.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TypeError {
+    expected: ValueType,
+    found: ValueType,
+}
+
+impl TypeError {
+    /// The type which was requested.
+    #[must_use]
+    pub fn expected(&self) -> ValueType {
+        self.expected
+    }
+    /// The actual type of the value.
+    #[must_use]
+    pub fn found(&self) -> ValueType {
+        self.found
+    }
+}
+
+impl Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
+impl Error for TypeError {}
+
+/// Error returned by [`IValue::f64_exact`] when the value cannot be converted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NumberError {
+    /// The value is not a number.
+    NotANumber(TypeError),
+    /// The value is a number, but cannot be represented exactly.
+    Inexact,
+}
+
+impl Display for NumberError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotANumber(e) => Display::fmt(e, f),
+            Self::Inexact => f.write_str("number cannot be represented exactly"),
+        }
+    }
+}
+
+impl Error for NumberError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::NotANumber(e) => Some(e),
+            Self::Inexact => None,
+        }
+    }
+}
+
 unsafe impl Send for IValue {}
 unsafe impl Sync for IValue {}
 
@@ -232,6 +304,35 @@ impl IValue {
     /// JSON `true`.
     pub const TRUE: Self = unsafe { Self::new_inline(TypeTag::ObjectOrTrue) };
 
+    /// Returns JSON `null`. Equivalent to [`IValue::NULL`].
+    #[inline]
+    #[must_use]
+    pub const fn null() -> Self {
+        Self::NULL
+    }
+    /// Returns the JSON boolean `b`. Does not allocate.
+    #[inline]
+    #[must_use]
+    pub const fn bool(b: bool) -> Self {
+        if b {
+            Self::TRUE
+        } else {
+            Self::FALSE
+        }
+    }
+    /// Returns an empty JSON array. Does not allocate.
+    #[inline]
+    #[must_use]
+    pub fn array() -> Self {
+        IArray::new().0
+    }
+    /// Returns an empty JSON object. Does not allocate.
+    #[inline]
+    #[must_use]
+    pub fn object() -> Self {
+        IObject::new().0
+    }
+
     pub(crate) fn ptr_usize(&self) -> usize {
         self.ptr.as_ptr() as usize
     }
@@ -256,6 +357,7 @@ impl IValue {
     pub(crate) fn raw_eq(&self, other: &Self) -> bool {
         self.ptr == other.ptr
     }
+    #[cfg(not(feature = "direct_strings"))]
     pub(crate) fn raw_hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.ptr.hash(state);
     }
@@ -285,6 +387,267 @@ impl IValue {
         }
     }
 
+    /// Returns `true` if this value has the same [`ValueType`] as `other`.
+    #[must_use]
+    pub fn same_type_as(&self, other: &IValue) -> bool {
+        self.type_() == other.type_()
+    }
+
+    /// Returns `true` if this is not an array or object.
+    #[must_use]
+    pub fn is_scalar(&self) -> bool {
+        !self.is_array() && !self.is_object()
+    }
+
+    /// Returns a cheap, structurally-shared read-only snapshot of this value.
+    ///
+    /// Unlike [`IValue::clone`], this does not copy arrays or objects: their
+    /// allocations are shared with the snapshot and only copied once either
+    /// side is mutated. See [`IArray::snapshot`] for details.
+    ///
+    /// Requires the `snapshot` feature.
+    #[cfg(feature = "snapshot")]
+    #[must_use]
+    pub fn snapshot(&self) -> IValue {
+        match self.type_() {
+            // Safety: We checked the type
+            ValueType::Array => unsafe { self.as_array_unchecked() }.snapshot().0,
+            ValueType::Object => unsafe { self.as_object_unchecked() }.snapshot().0,
+            ValueType::Null | ValueType::Bool | ValueType::String | ValueType::Number => {
+                self.clone()
+            }
+        }
+    }
+
+    /// Merges `overlay` into this value like a JSON merge patch, except that
+    /// arrays are merged element-wise rather than replaced.
+    ///
+    /// Objects are merged recursively, and a `null` in an overlay object removes
+    /// the corresponding key. When both sides are arrays, each item of the overlay
+    /// is merged into the item at the same index, and any extra items are
+    /// appended. Any other value in `overlay` replaces this one.
+    ///
+    /// Note that this diverges from RFC 7386, where arrays are always replaced.
+    pub fn merge_arrays_by_index(&mut self, overlay: &IValue) {
+        match (self.destructure_mut(), overlay.destructure_ref()) {
+            (DestructuredMut::Object(obj), DestructuredRef::Object(other)) => {
+                for (k, v) in other {
+                    if v.is_null() {
+                        obj.remove(k);
+                    } else if let Some(dest) = obj.get_mut(k) {
+                        dest.merge_arrays_by_index(v);
+                    } else {
+                        obj.insert(k.clone(), v.clone());
+                    }
+                }
+            }
+            (DestructuredMut::Array(arr), DestructuredRef::Array(other)) => {
+                let len = arr.len();
+                for (dest, v) in arr.iter_mut().zip(other) {
+                    dest.merge_arrays_by_index(v);
+                }
+                arr.extend(other.iter().skip(len).cloned());
+            }
+            _ => *self = overlay.clone(),
+        }
+    }
+
+    /// Replaces every floating point number within this value which has an
+    /// integral value, such as `2.0`, with the equivalent integer.
+    ///
+    /// Afterwards, [`INumber::has_decimal_point`] returns `false` for those
+    /// numbers, and they are serialized without a decimal point. Integral
+    /// values which are too large for an `i64` or `u64` are left unchanged.
+    pub fn normalize_numbers(&mut self) {
+        // Every integral float in this range fits in an `i64` or a `u64`
+        const MIN: f64 = -9_223_372_036_854_775_808.0;
+        const MAX: f64 = 18_446_744_073_709_551_616.0;
+
+        let mut nodes = self.nodes_mut();
+        while let Some(node) = nodes.next() {
+            let v = match node.as_number() {
+                Some(n) if n.has_decimal_point() => n.to_f64(),
+                _ => continue,
+            };
+            if let Some(v) = v.filter(|v| v.fract() == 0.0 && *v >= MIN && *v < MAX) {
+                *node = if v < 0.0 {
+                    (v as i64).into()
+                } else {
+                    (v as u64).into()
+                };
+            }
+        }
+    }
+
+    /// Truncates every string within this value to at most `max_len` bytes.
+    ///
+    /// Strings are truncated on a character boundary, so may end up slightly
+    /// shorter than `max_len`. Object keys are left unchanged.
+    pub fn truncate_strings(&mut self, max_len: usize) {
+        let mut nodes = self.nodes_mut();
+        while let Some(node) = nodes.next() {
+            let s = match node.as_string() {
+                Some(s) if s.len() > max_len => s.as_str(),
+                _ => continue,
+            };
+            let mut end = max_len;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            *node = IString::intern(&s[..end]).into();
+        }
+    }
+
+    /// Attempts to convert this value to the `target` type, returning `None`
+    /// if there is no sensible conversion.
+    ///
+    /// A value which already has the `target` type is cloned. Otherwise, rows
+    /// give the type of this value and columns the `target` type:
+    ///
+    /// |           | Bool               | Number          | String             | Array              |
+    /// |-----------|--------------------|-----------------|--------------------|--------------------|
+    /// | Null      | -                  | -               | -                  | -                  |
+    /// | Bool      |                    | -               | `"true"`/`"false"` | one-item array     |
+    /// | Number    | -                  |                 | JSON text          | one-item array     |
+    /// | String    | `"true"`/`"false"` | parsed as JSON  |                    | one-item array     |
+    /// | Object    | -                  | -               | -                  | one-item array     |
+    ///
+    /// Nothing converts to `null` or to an object other than itself, and
+    /// arrays only convert to themselves. Strings are parsed as numbers after
+    /// trimming surrounding whitespace.
+    #[must_use]
+    pub fn coerce(&self, target: ValueType) -> Option<IValue> {
+        if self.type_() == target {
+            return Some(self.clone());
+        }
+        match (self.destructure_ref(), target) {
+            (DestructuredRef::Null, _) | (DestructuredRef::Array(_), _) => None,
+            (_, ValueType::Array) => Some(IArray::from(vec![self.clone()]).into()),
+            (DestructuredRef::String(s), ValueType::Bool) => match s.as_str() {
+                "true" => Some(IValue::TRUE),
+                "false" => Some(IValue::FALSE),
+                _ => None,
+            },
+            (DestructuredRef::String(s), ValueType::Number) => {
+                serde_json::from_str::<INumber>(s.trim())
+                    .ok()
+                    .map(Into::into)
+            }
+            (DestructuredRef::Bool(b), ValueType::String) => {
+                Some(if b { "true" } else { "false" }.into())
+            }
+            (DestructuredRef::Number(n), ValueType::String) => {
+                serde_json::to_string(n).ok().map(Into::into)
+            }
+            _ => None,
+        }
+    }
+
+    /// Compares two values like `==`, except that arrays, including nested
+    /// arrays, are compared as multisets, ignoring the order of their items.
+    ///
+    /// Array items are matched up by a linear search for an equal item which
+    /// has not already been matched, so this takes O(n²) comparisons for
+    /// arrays of length n. Sorting is not an option, since objects have no
+    /// ordering, and items would need to be compared as multisets themselves.
+    #[must_use]
+    pub fn eq_unordered(&self, other: &IValue) -> bool {
+        match (self.destructure_ref(), other.destructure_ref()) {
+            (DestructuredRef::Array(a), DestructuredRef::Array(b)) => {
+                if a.len() != b.len() {
+                    return false;
+                }
+                let mut matched = vec![false; b.len()];
+                a.iter().all(|x| {
+                    let found = b
+                        .iter()
+                        .zip(&matched)
+                        .position(|(y, &m)| !m && x.eq_unordered(y));
+                    if let Some(i) = found {
+                        matched[i] = true;
+                        true
+                    } else {
+                        false
+                    }
+                })
+            }
+            (DestructuredRef::Object(a), DestructuredRef::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(k, x)| b.get(k).filter(|y| x.eq_unordered(y)).is_some())
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Returns `true` if `other` contains everything in this value.
+    ///
+    /// An object is a subset if every key is present in `other` with a value
+    /// which is itself a subset, and an array is a subset if it is no longer
+    /// than `other` and each item is a subset of the item at the same index.
+    /// Other values must be equal.
+    #[must_use]
+    pub fn is_subset_of(&self, other: &IValue) -> bool {
+        match (self.destructure_ref(), other.destructure_ref()) {
+            (DestructuredRef::Array(a), DestructuredRef::Array(b)) => {
+                a.len() <= b.len() && a.iter().zip(b.iter()).all(|(x, y)| x.is_subset_of(y))
+            }
+            (DestructuredRef::Object(a), DestructuredRef::Object(b)) => a
+                .iter()
+                .all(|(k, x)| b.get(k).filter(|y| x.is_subset_of(y)).is_some()),
+            _ => self == other,
+        }
+    }
+
+    /// Compares two values like `==`, except that numbers, including nested
+    /// numbers, only need to be within `epsilon` of each other.
+    ///
+    /// Two numbers are considered equal if their difference is at most
+    /// `epsilon`, or at most `epsilon` times the larger of their magnitudes.
+    /// This means `epsilon` acts as an absolute tolerance for numbers near
+    /// zero, and as a relative tolerance for large numbers. Numbers are
+    /// compared as `f64`s, so may lose precision.
+    #[must_use]
+    pub fn approx_eq(&self, other: &IValue, epsilon: f64) -> bool {
+        match (self.destructure_ref(), other.destructure_ref()) {
+            (DestructuredRef::Number(a), DestructuredRef::Number(b)) => {
+                let (a, b) = (a.to_f64_lossy(), b.to_f64_lossy());
+                let diff = (a - b).abs();
+                diff <= epsilon || diff <= epsilon * a.abs().max(b.abs())
+            }
+            (DestructuredRef::Array(a), DestructuredRef::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.approx_eq(y, epsilon))
+            }
+            (DestructuredRef::Object(a), DestructuredRef::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(k, x)| b.get(k).filter(|y| x.approx_eq(y, epsilon)).is_some())
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Returns `true` if any string within this value, including object keys,
+    /// contains `needle`.
+    #[must_use]
+    pub fn any_string_contains(&self, needle: &str) -> bool {
+        match self.destructure_ref() {
+            DestructuredRef::String(s) => s.contains(needle),
+            DestructuredRef::Array(arr) => arr.iter().any(|v| v.any_string_contains(needle)),
+            DestructuredRef::Object(obj) => obj
+                .iter()
+                .any(|(k, v)| k.contains(needle) || v.any_string_contains(needle)),
+            _ => false,
+        }
+    }
+
+    fn type_error(&self, expected: ValueType) -> TypeError {
+        TypeError {
+            expected,
+            found: self.type_(),
+        }
+    }
+
     /// Destructures this value into an enum which can be `match`ed on.
     #[must_use]
     pub fn destructure(self) -> Destructured {
@@ -300,7 +663,7 @@ impl IValue {
 
     /// Destructures a reference to this value into an enum which can be `match`ed on.
     #[must_use]
-    pub fn destructure_ref(&self) -> DestructuredRef {
+    pub fn destructure_ref(&self) -> DestructuredRef<'_> {
         // Safety: we check the type
         unsafe {
             match self.type_() {
@@ -315,7 +678,7 @@ impl IValue {
     }
 
     /// Destructures a mutable reference to this value into an enum which can be `match`ed on.
-    pub fn destructure_mut(&mut self) -> DestructuredMut {
+    pub fn destructure_mut(&mut self) -> DestructuredMut<'_> {
         // Safety: we check the type
         unsafe {
             match self.type_() {
@@ -349,6 +712,22 @@ impl IValue {
         index.index_into_mut(self)
     }
 
+    /// Mutably indexes into this value with a number or string, creating the
+    /// value if it does not exist.
+    ///
+    /// A `null` is first replaced with an empty array or object, depending on
+    /// the type of the index. Missing object keys are then inserted with a
+    /// `null` value, and arrays are padded with `null`s up to and including
+    /// the index, so this never fails for an out-of-range array index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is not an array, object or `null`, or if attempting to
+    /// index an array with a string or an object with a number.
+    pub fn get_mut_or_null(&mut self, index: impl ValueIndex) -> &mut IValue {
+        index.index_or_null(self)
+    }
+
     /// Removes a value at the specified numberic or string index.
     /// Panics if this is not an array or object.
     /// Panics if attempting to index an array with a string.
@@ -364,6 +743,24 @@ impl IValue {
         mem::replace(self, IValue::NULL)
     }
 
+    /// Replaces this value with `new`, returning the old value.
+    pub fn replace(&mut self, new: IValue) -> IValue {
+        mem::replace(self, new)
+    }
+
+    /// Overwrites this value with `new`, dropping the old value.
+    ///
+    /// This is equivalent to `*self = new`: the contents are replaced in place,
+    /// so the address of this `IValue` is unchanged.
+    pub fn set(&mut self, new: impl Into<IValue>) {
+        *self = new.into();
+    }
+
+    /// Swaps this value with `other`, without cloning either.
+    pub fn swap(&mut self, other: &mut IValue) {
+        mem::swap(self, other);
+    }
+
     /// Returns the length of this value if it is an array or object.
     /// Returns `None` for other types.
     #[must_use]
@@ -427,6 +824,23 @@ impl IValue {
         }
     }
 
+    /// Converts this value to a `bool`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TypeError`] if it's not a boolean.
+    pub fn try_to_bool(&self) -> Result<bool, TypeError> {
+        self.to_bool()
+            .ok_or_else(|| self.type_error(ValueType::Bool))
+    }
+
+    /// Converts this value to a `bool`, or returns `default` if it's not a
+    /// boolean.
+    #[must_use]
+    pub fn bool_or(&self, default: bool) -> bool {
+        self.to_bool().unwrap_or(default)
+    }
+
     // # Number methods
     /// Returns `true` if this is a number.
     #[must_use]
@@ -475,6 +889,26 @@ impl IValue {
         }
     }
 
+    /// Gets a reference to this value as an [`INumber`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TypeError`] if it's not a number.
+    pub fn try_as_number(&self) -> Result<&INumber, TypeError> {
+        self.as_number()
+            .ok_or_else(|| self.type_error(ValueType::Number))
+    }
+
+    /// Gets a mutable reference to this value as an [`INumber`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TypeError`] if it's not a number.
+    pub fn try_as_number_mut(&mut self) -> Result<&mut INumber, TypeError> {
+        let err = self.type_error(ValueType::Number);
+        self.as_number_mut().ok_or(err)
+    }
+
     /// Converts this value to an [`INumber`].
     ///
     /// # Errors
@@ -488,11 +922,24 @@ impl IValue {
         }
     }
 
+    /// Returns which primitive type this value is stored as, if it is a number.
+    /// See [`INumber::classify`].
+    #[must_use]
+    pub fn number_class(&self) -> Option<NumberClass> {
+        Some(self.as_number()?.classify())
+    }
+
     /// Converts this value to an i64 if it is a number that can be represented exactly.
     #[must_use]
     pub fn to_i64(&self) -> Option<i64> {
         self.as_number()?.to_i64()
     }
+    /// Converts this value to an i64, or returns `default` if it is not a
+    /// number that can be represented exactly.
+    #[must_use]
+    pub fn i64_or(&self, default: i64) -> i64 {
+        self.to_i64().unwrap_or(default)
+    }
     /// Converts this value to a u64 if it is a number that can be represented exactly.
     #[must_use]
     pub fn to_u64(&self) -> Option<u64> {
@@ -503,6 +950,18 @@ impl IValue {
     pub fn to_f64(&self) -> Option<f64> {
         self.as_number()?.to_f64()
     }
+    /// Converts this value to an f64 if it is a number that can be represented exactly.
+    ///
+    /// # Errors
+    ///
+    /// Unlike [`IValue::to_f64`], distinguishes between a value which is not a
+    /// number, and a number which would lose precision as an f64.
+    pub fn f64_exact(&self) -> Result<f64, NumberError> {
+        let n = self
+            .as_number()
+            .ok_or_else(|| NumberError::NotANumber(self.type_error(ValueType::Number)))?;
+        n.to_f64().ok_or(NumberError::Inexact)
+    }
     /// Converts this value to an f32 if it is a number that can be represented exactly.
     #[must_use]
     pub fn to_f32(&self) -> Option<f32> {
@@ -528,6 +987,23 @@ impl IValue {
     pub fn to_usize(&self) -> Option<usize> {
         self.as_number()?.to_usize()
     }
+
+    /// Converts a byte slice to an array of numbers, in the same way as it is
+    /// serialized by [`to_value`](crate::to_value).
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        bytes.iter().copied().collect::<IArray>().into()
+    }
+    /// Converts this value to a `Vec<u8>` if it is an array of numbers which
+    /// can each be represented exactly as a `u8`. This is the inverse of
+    /// [`IValue::from_bytes`].
+    #[must_use]
+    pub fn as_byte_vec(&self) -> Option<Vec<u8>> {
+        self.as_array()?
+            .iter()
+            .map(|v| v.to_u32().and_then(|n| u8::try_from(n).ok()))
+            .collect()
+    }
     /// Converts this value to an f64 if it is a number, potentially losing precision
     /// in the process.
     #[must_use]
@@ -581,6 +1057,32 @@ impl IValue {
         }
     }
 
+    /// Gets a reference to this value as an [`IString`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TypeError`] if it's not a string.
+    pub fn try_as_string(&self) -> Result<&IString, TypeError> {
+        self.as_string()
+            .ok_or_else(|| self.type_error(ValueType::String))
+    }
+
+    /// Gets this value as a `&str`, or returns `default` if it's not a string.
+    #[must_use]
+    pub fn str_or<'a>(&'a self, default: &'a str) -> &'a str {
+        self.as_string().map_or(default, IString::as_str)
+    }
+
+    /// Gets a mutable reference to this value as an [`IString`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TypeError`] if it's not a string.
+    pub fn try_as_string_mut(&mut self) -> Result<&mut IString, TypeError> {
+        let err = self.type_error(ValueType::String);
+        self.as_string_mut().ok_or(err)
+    }
+
     /// Converts this value to an [`IString`].
     ///
     /// # Errors
@@ -623,6 +1125,13 @@ impl IValue {
         }
     }
 
+    /// Gets the items of this value if it's an array, or an empty slice
+    /// otherwise.
+    #[must_use]
+    pub fn as_array_or_empty(&self) -> &[IValue] {
+        self.as_array().map_or(&[], IArray::as_slice)
+    }
+
     /// Gets a mutable reference to this value as an [`IArray`].
     /// Returns `None` if it's not an array.
     pub fn as_array_mut(&mut self) -> Option<&mut IArray> {
@@ -634,6 +1143,26 @@ impl IValue {
         }
     }
 
+    /// Gets a reference to this value as an [`IArray`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TypeError`] if it's not an array.
+    pub fn try_as_array(&self) -> Result<&IArray, TypeError> {
+        self.as_array()
+            .ok_or_else(|| self.type_error(ValueType::Array))
+    }
+
+    /// Gets a mutable reference to this value as an [`IArray`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TypeError`] if it's not an array.
+    pub fn try_as_array_mut(&mut self) -> Result<&mut IArray, TypeError> {
+        let err = self.type_error(ValueType::Array);
+        self.as_array_mut().ok_or(err)
+    }
+
     /// Converts this value to an [`IArray`].
     ///
     /// # Errors
@@ -676,6 +1205,12 @@ impl IValue {
         }
     }
 
+    /// Returns an iterator over the (&key, &value) pairs of this value if it's
+    /// an object, or an empty iterator otherwise.
+    pub fn as_object_entries_or_empty(&self) -> impl Iterator<Item = (&IString, &IValue)> {
+        self.as_object().into_iter().flat_map(IObject::iter)
+    }
+
     /// Gets a mutable reference to this value as an [`IObject`].
     /// Returns `None` if it's not an object.
     pub fn as_object_mut(&mut self) -> Option<&mut IObject> {
@@ -687,6 +1222,26 @@ impl IValue {
         }
     }
 
+    /// Gets a reference to this value as an [`IObject`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TypeError`] if it's not an object.
+    pub fn try_as_object(&self) -> Result<&IObject, TypeError> {
+        self.as_object()
+            .ok_or_else(|| self.type_error(ValueType::Object))
+    }
+
+    /// Gets a mutable reference to this value as an [`IObject`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TypeError`] if it's not an object.
+    pub fn try_as_object_mut(&mut self) -> Result<&mut IObject, TypeError> {
+        let err = self.type_error(ValueType::Object);
+        self.as_object_mut().ok_or(err)
+    }
+
     /// Converts this value to an [`IObject`].
     ///
     /// # Errors
@@ -713,6 +1268,24 @@ impl Clone for IValue {
             ValueType::Number => unsafe { self.as_number_unchecked() }.clone_impl(),
         }
     }
+
+    /// Arrays and objects reuse the existing allocations of `self` where
+    /// possible, which also makes [`ToOwned::clone_into`] cheaper when copying
+    /// into a similarly shaped value.
+    fn clone_from(&mut self, source: &Self) {
+        match (self.type_(), source.type_()) {
+            // Safety: We checked the types
+            (ValueType::Array, ValueType::Array) => unsafe {
+                self.as_array_unchecked_mut()
+                    .clone_from_impl(source.as_array_unchecked());
+            },
+            (ValueType::Object, ValueType::Object) => unsafe {
+                self.as_object_unchecked_mut()
+                    .clone_from_impl(source.as_object_unchecked());
+            },
+            _ => *self = source.clone(),
+        }
+    }
 }
 
 impl Drop for IValue {
@@ -732,8 +1305,10 @@ impl Drop for IValue {
 impl Hash for IValue {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match self.type_() {
-            // Inline and interned types can be trivially hashed
-            ValueType::Null | ValueType::Bool | ValueType::String => self.ptr.hash(state),
+            // Inline types can be trivially hashed
+            ValueType::Null | ValueType::Bool => self.ptr.hash(state),
+            // Safety: We checked the type
+            ValueType::String => unsafe { self.as_string_unchecked() }.hash(state),
             // Safety: We checked the type
             ValueType::Array => unsafe { self.as_array_unchecked() }.hash(state),
             // Safety: We checked the type
@@ -751,8 +1326,9 @@ impl PartialEq for IValue {
             // Safety: Only methods for the appropriate type are called
             unsafe {
                 match t1 {
-                    // Inline and interned types can be trivially compared
-                    ValueType::Null | ValueType::Bool | ValueType::String => self.ptr == other.ptr,
+                    // Inline types can be trivially compared
+                    ValueType::Null | ValueType::Bool => self.ptr == other.ptr,
+                    ValueType::String => self.as_string_unchecked() == other.as_string_unchecked(),
                     ValueType::Number => self.as_number_unchecked() == other.as_number_unchecked(),
                     ValueType::Array => self.as_array_unchecked() == other.as_array_unchecked(),
                     ValueType::Object => self.as_object_unchecked() == other.as_object_unchecked(),
@@ -799,6 +1375,8 @@ mod private {
     impl Sealed for usize {}
     impl Sealed for &str {}
     impl Sealed for &super::IString {}
+    impl Sealed for &String {}
+    impl Sealed for &std::borrow::Cow<'_, str> {}
     impl<T: Sealed> Sealed for &T {}
 }
 
@@ -814,10 +1392,32 @@ pub trait ValueIndex: private::Sealed + Copy {
     #[doc(hidden)]
     fn index_or_insert(self, v: &mut IValue) -> &mut IValue;
 
+    #[doc(hidden)]
+    fn index_or_null(self, v: &mut IValue) -> &mut IValue;
+
     #[doc(hidden)]
     fn remove(self, v: &mut IValue) -> Option<IValue>;
 }
 
+// Used by `IValue::get_mut_or_null` to replace a `null` with an empty array or object.
+fn array_or_null(v: &mut IValue) -> &mut IArray {
+    if v.is_null() {
+        *v = IArray::new().into();
+    }
+    let found = v.type_();
+    v.as_array_mut()
+        .unwrap_or_else(|| panic!("cannot index into {} with a number", found))
+}
+
+fn object_or_null(v: &mut IValue) -> &mut IObject {
+    if v.is_null() {
+        *v = IObject::new().into();
+    }
+    let found = v.type_();
+    v.as_object_mut()
+        .unwrap_or_else(|| panic!("cannot index into {} with a string", found))
+}
+
 impl ValueIndex for usize {
     fn index_into(self, v: &IValue) -> Option<&IValue> {
         v.as_array().unwrap().get(self)
@@ -831,6 +1431,17 @@ impl ValueIndex for usize {
         self.index_into_mut(v).unwrap()
     }
 
+    fn index_or_null(self, v: &mut IValue) -> &mut IValue {
+        let arr = array_or_null(v);
+        if self >= arr.len() {
+            arr.reserve(self + 1 - arr.len());
+            while arr.len() <= self {
+                arr.push(IValue::NULL);
+            }
+        }
+        &mut arr[self]
+    }
+
     fn remove(self, v: &mut IValue) -> Option<IValue> {
         v.as_array_mut().unwrap().remove(self)
     }
@@ -849,11 +1460,46 @@ impl ValueIndex for &str {
         &mut v.as_object_mut().unwrap()[self]
     }
 
+    fn index_or_null(self, v: &mut IValue) -> &mut IValue {
+        &mut object_or_null(v)[self]
+    }
+
     fn remove(self, v: &mut IValue) -> Option<IValue> {
         v.as_object_mut().unwrap().remove(self)
     }
 }
 
+// Other string types are indexed via `&str`.
+macro_rules! impl_value_index_via_str {
+    ($($t:ty),*) => {
+        $(
+            impl ValueIndex for $t {
+                fn index_into(self, v: &IValue) -> Option<&IValue> {
+                    (&**self).index_into(v)
+                }
+
+                fn index_into_mut(self, v: &mut IValue) -> Option<&mut IValue> {
+                    (&**self).index_into_mut(v)
+                }
+
+                fn index_or_insert(self, v: &mut IValue) -> &mut IValue {
+                    (&**self).index_or_insert(v)
+                }
+
+                fn index_or_null(self, v: &mut IValue) -> &mut IValue {
+                    (&**self).index_or_null(v)
+                }
+
+                fn remove(self, v: &mut IValue) -> Option<IValue> {
+                    (&**self).remove(v)
+                }
+            }
+        )*
+    };
+}
+
+impl_value_index_via_str!(&String, &Cow<'_, str>);
+
 impl ValueIndex for &IString {
     fn index_into(self, v: &IValue) -> Option<&IValue> {
         v.as_object().unwrap().get(self)
@@ -867,6 +1513,10 @@ impl ValueIndex for &IString {
         &mut v.as_object_mut().unwrap()[self]
     }
 
+    fn index_or_null(self, v: &mut IValue) -> &mut IValue {
+        &mut object_or_null(v)[self]
+    }
+
     fn remove(self, v: &mut IValue) -> Option<IValue> {
         v.as_object_mut().unwrap().remove(self)
     }
@@ -885,6 +1535,10 @@ impl<T: ValueIndex> ValueIndex for &T {
         (*self).index_or_insert(v)
     }
 
+    fn index_or_null(self, v: &mut IValue) -> &mut IValue {
+        (*self).index_or_null(v)
+    }
+
     fn remove(self, v: &mut IValue) -> Option<IValue> {
         (*self).remove(v)
     }
@@ -975,6 +1629,18 @@ impl From<f64> for IValue {
     }
 }
 
+impl FromIterator<IValue> for IValue {
+    fn from_iter<T: IntoIterator<Item = IValue>>(iter: T) -> Self {
+        IArray::from_iter(iter).into()
+    }
+}
+
+impl FromIterator<(IString, IValue)> for IValue {
+    fn from_iter<T: IntoIterator<Item = (IString, IValue)>>(iter: T) -> Self {
+        IObject::from_iter(iter).into()
+    }
+}
+
 impl Default for IValue {
     fn default() -> Self {
         Self::NULL
@@ -1117,4 +1783,382 @@ mod tests {
 
         assert_eq!(x.into_object(), Ok(o));
     }
+
+    #[test]
+    fn can_construct_with_functions() {
+        const T: IValue = IValue::bool(true);
+
+        assert!(IValue::null().is_null());
+        assert_eq!(T, IValue::TRUE);
+        assert_eq!(IValue::bool(false), IValue::FALSE);
+        assert_eq!(IValue::array().type_(), ValueType::Array);
+        assert_eq!(IValue::array().len(), Some(0));
+        assert_eq!(IValue::object().type_(), ValueType::Object);
+        assert_eq!(IValue::object().len(), Some(0));
+    }
+
+    #[mockalloc::test]
+    fn try_as_reports_type_error() {
+        let mut x: IValue = "hello".into();
+        let err = x.try_as_number().unwrap_err();
+
+        assert_eq!(err.expected(), ValueType::Number);
+        assert_eq!(err.found(), ValueType::String);
+        assert_eq!(err.to_string(), "expected number, found string");
+        assert_eq!(
+            x.try_as_array_mut().unwrap_err().expected(),
+            ValueType::Array
+        );
+        assert_eq!(
+            IValue::NULL.try_to_bool().unwrap_err().found(),
+            ValueType::Null
+        );
+        assert_eq!(x.try_as_string().unwrap().as_str(), "hello");
+    }
+
+    #[mockalloc::test]
+    fn clone_into_matches_source() {
+        let x: IValue = ijson!({ "a": [1, 2, { "b": "c" }], "d": null });
+        let mut y: IValue = ijson!({ "d": [true], "e": 1 });
+        x.clone_into(&mut y);
+        assert_eq!(x, y);
+        assert_eq!(y.as_object().unwrap().keys().next().unwrap(), "a");
+
+        let mut z: IValue = ijson!([1, 2, 3, 4]);
+        x["a"].clone_into(&mut z);
+        assert_eq!(x["a"], z);
+    }
+
+    #[test]
+    fn clone_into_reuses_allocations() {
+        let x: IValue = ijson!({ "a": [1, 2, { "b": "c" }], "d": [null, true] });
+        let mut dest = x.clone();
+        dest["a"][2]["b"] = "e".into();
+        dest["d"][0] = IValue::FALSE;
+
+        let fresh = mockalloc::record_allocs(|| {
+            drop(x.clone());
+        });
+        let reused = mockalloc::record_allocs(|| {
+            x.clone_into(&mut dest);
+        });
+
+        assert!(fresh.num_allocs() > 0);
+        assert_eq!(reused.num_allocs(), 0);
+        assert_eq!(dest, x);
+    }
+
+    // Spawning a thread frees memory across threads, which mockalloc rejects
+    #[cfg(feature = "snapshot")]
+    #[test]
+    fn snapshot_can_be_read_from_another_thread() {
+        let mut x: IValue = ijson!({ "points": [1, 2, 3] });
+        let y = x.snapshot();
+
+        let handle = std::thread::spawn(move || y["points"].len());
+        x["points"].as_array_mut().unwrap().clear();
+
+        assert_eq!(handle.join().unwrap(), Some(3));
+        assert_eq!(x["points"].len(), Some(0));
+    }
+
+    #[mockalloc::test]
+    fn can_index_with_owned_strings() {
+        let x: IValue = ijson!({"foo": [1, 2]});
+        let owned = String::from("foo");
+        let cow: Cow<str> = Cow::Borrowed("foo");
+
+        assert_eq!(x[&owned][1], IValue::from(2));
+        assert_eq!(x.get(&cow), x.get("foo"));
+    }
+
+    #[mockalloc::test]
+    fn can_merge_arrays_by_index() {
+        let mut x: IValue = ijson!([{"a": 1}]);
+        x.merge_arrays_by_index(&ijson!([{"b": 2}]));
+        assert_eq!(x, ijson!([{"a": 1, "b": 2}]));
+
+        let mut x: IValue = ijson!({"a": [1, [2, 3]], "b": true, "c": "d"});
+        x.merge_arrays_by_index(&ijson!({"a": [4, [5], {"e": 6}], "b": null, "f": [7]}));
+        assert_eq!(x, ijson!({"a": [4, [5, 3], {"e": 6}], "c": "d", "f": [7]}));
+    }
+
+    #[mockalloc::test]
+    fn can_normalize_numbers() {
+        let mut x: IValue = ijson!({"a": [2.0, 2.5, -3.0, 1e19, 1e20], "b": 7});
+        x.normalize_numbers();
+
+        assert!(!x["a"][0].as_number().unwrap().has_decimal_point());
+        assert_eq!(x["a"][0].to_i64(), Some(2));
+        assert!(x["a"][1].as_number().unwrap().has_decimal_point());
+        assert_eq!(x["a"][1].to_f64(), Some(2.5));
+        assert_eq!(x["a"][2].to_i64(), Some(-3));
+        assert_eq!(x["a"][3].to_u64(), Some(10_000_000_000_000_000_000));
+        assert!(x["a"][4].as_number().unwrap().has_decimal_point());
+        assert_eq!(
+            serde_json::to_string(&x).unwrap(),
+            r#"{"a":[2,2.5,-3,10000000000000000000,1e+20],"b":7}"#
+        );
+    }
+
+    #[mockalloc::test]
+    fn can_round_trip_bytes() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let x = IValue::from_bytes(&bytes);
+
+        struct Bytes<'a>(&'a [u8]);
+        impl serde::Serialize for Bytes<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(self.0)
+            }
+        }
+        assert_eq!(crate::to_value(Bytes(&bytes)).unwrap(), x);
+        assert_eq!(x.len(), Some(256));
+        assert_eq!(x.as_byte_vec(), Some(bytes));
+        assert_eq!(IValue::from_bytes(&[]).as_byte_vec(), Some(Vec::new()));
+        assert_eq!(ijson!([1, 256]).as_byte_vec(), None);
+        assert_eq!(ijson!([1, -1]).as_byte_vec(), None);
+        assert_eq!(ijson!([1, 1.5]).as_byte_vec(), None);
+        assert_eq!(ijson!("abc").as_byte_vec(), None);
+    }
+
+    #[mockalloc::test]
+    fn can_get_mut_or_null() {
+        let mut x: IValue = ijson!({"a": [1]});
+        *x.get_mut_or_null("b") = true.into();
+        *x.get_mut_or_null("a").get_mut_or_null(3) = 2.into();
+        *x.get_mut_or_null("c")
+            .get_mut_or_null(1)
+            .get_mut_or_null("d") = 3.into();
+        assert_eq!(
+            x,
+            ijson!({"a": [1, null, null, 2], "b": true, "c": [null, {"d": 3}]})
+        );
+
+        *x.get_mut_or_null("a").get_mut_or_null(0) = 4.into();
+        assert_eq!(x["a"], ijson!([4, null, null, 2]));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot index into number with a string")]
+    fn get_mut_or_null_rejects_scalars() {
+        let mut x: IValue = ijson!(1);
+        x.get_mut_or_null("a");
+    }
+
+    #[mockalloc::test]
+    fn can_get_items_or_empty() {
+        let arr: IValue = ijson!([1, 2]);
+        let obj: IValue = ijson!({"a": 1});
+
+        assert_eq!(arr.as_array_or_empty(), &[IValue::from(1), IValue::from(2)]);
+        assert!(obj.as_array_or_empty().is_empty());
+        assert!(IValue::NULL.as_array_or_empty().is_empty());
+
+        let entries: Vec<_> = obj.as_object_entries_or_empty().collect();
+        assert_eq!(entries, [(&IString::intern("a"), &IValue::from(1))]);
+        assert_eq!(arr.as_object_entries_or_empty().count(), 0);
+        assert_eq!(IValue::TRUE.as_object_entries_or_empty().count(), 0);
+    }
+
+    #[mockalloc::test]
+    fn can_compare_types() {
+        assert!(IValue::from(1).same_type_as(&IValue::from(2)));
+        assert!(IValue::from(1).same_type_as(&IValue::from(2.5)));
+        assert!(IValue::TRUE.same_type_as(&IValue::FALSE));
+        assert!(!IValue::NULL.same_type_as(&IValue::FALSE));
+        assert!(!ijson!([]).same_type_as(&ijson!({})));
+
+        assert!(IValue::NULL.is_scalar());
+        assert!(IValue::TRUE.is_scalar());
+        assert!(IValue::from(1.5).is_scalar());
+        assert!(IValue::from("a").is_scalar());
+        assert!(!ijson!([1]).is_scalar());
+        assert!(!ijson!({}).is_scalar());
+    }
+
+    #[mockalloc::test]
+    fn can_truncate_strings() {
+        let mut x: IValue = ijson!({
+            "long key which is kept": ["abcdef", "abc", 1],
+            "b": "añb",
+            "c": "ñññ"
+        });
+        x.truncate_strings(3);
+
+        assert_eq!(
+            x,
+            ijson!({"long key which is kept": ["abc", "abc", 1], "b": "añ", "c": "ñ"})
+        );
+    }
+
+    #[mockalloc::test]
+    fn can_get_with_defaults() {
+        let x = ijson!({"flag": true, "count": 3, "name": "foo", "big": 1e30});
+
+        assert!(x["flag"].bool_or(false));
+        assert_eq!(x["count"].i64_or(0), 3);
+        assert_eq!(x["name"].str_or("bar"), "foo");
+
+        assert!(x["count"].bool_or(true));
+        assert_eq!(x["name"].i64_or(7), 7);
+        assert_eq!(x["big"].i64_or(7), 7);
+        assert_eq!(x["flag"].str_or("bar"), "bar");
+
+        let null = IValue::NULL;
+        let missing = x.get("missing").unwrap_or(&null);
+        assert!(!missing.bool_or(false));
+        assert_eq!(missing.i64_or(-1), -1);
+        assert_eq!(missing.str_or(""), "");
+    }
+
+    #[mockalloc::test]
+    fn can_search_strings() {
+        let x = ijson!({"a": [1, {"b": ["x", "needle in a haystack"]}], "key-match": null});
+
+        assert!(x.any_string_contains("in a hay"));
+        assert!(x.any_string_contains("-mat"));
+        assert!(!x.any_string_contains("absent"));
+        assert!(!ijson!(1).any_string_contains(""));
+        assert!(ijson!("").any_string_contains(""));
+    }
+
+    #[mockalloc::test]
+    fn can_replace_in_place() {
+        let mut x = ijson!({"a": [1, 2]});
+        let old = x.replace(ijson!("new"));
+        assert_eq!(old, ijson!({"a": [1, 2]}));
+        assert_eq!(x, ijson!("new"));
+
+        x.set(3);
+        assert_eq!(x, ijson!(3));
+
+        let mut y = ijson!([true]);
+        x.swap(&mut y);
+        assert_eq!(x, ijson!([true]));
+        assert_eq!(y, ijson!(3));
+    }
+
+    #[mockalloc::test]
+    fn can_get_number_class() {
+        assert_eq!(ijson!(1).number_class(), Some(NumberClass::Signed));
+        assert_eq!(
+            IValue::from(u64::MAX).number_class(),
+            Some(NumberClass::Unsigned)
+        );
+        assert_eq!(ijson!(1.5).number_class(), Some(NumberClass::Float));
+        assert_eq!(ijson!("1").number_class(), None);
+    }
+
+    #[mockalloc::test]
+    fn can_collect() {
+        let arr: IValue = (1..=3).map(IValue::from).collect();
+        let obj: IValue = vec!["a", "b"]
+            .into_iter()
+            .map(|k| (IString::intern(k), IValue::from(k)))
+            .collect();
+
+        assert_eq!(arr, ijson!([1, 2, 3]));
+        assert_eq!(obj, ijson!({"a": "a", "b": "b"}));
+    }
+
+    #[mockalloc::test]
+    fn can_coerce() {
+        use ValueType::*;
+
+        assert_eq!(ijson!(" 12.5 ").coerce(Number), Some(ijson!(12.5)));
+        assert_eq!(ijson!(-3).coerce(String), Some(ijson!("-3")));
+        assert_eq!(ijson!(true).coerce(String), Some(ijson!("true")));
+        assert_eq!(ijson!("false").coerce(Bool), Some(ijson!(false)));
+        assert_eq!(ijson!(1).coerce(Array), Some(ijson!([1])));
+        assert_eq!(ijson!({"a": 1}).coerce(Array), Some(ijson!([{"a": 1}])));
+        assert_eq!(ijson!([1]).coerce(Array), Some(ijson!([1])));
+
+        assert_eq!(ijson!("abc").coerce(Number), None);
+        assert_eq!(ijson!("[1]").coerce(Number), None);
+        assert_eq!(ijson!("yes").coerce(Bool), None);
+        assert_eq!(ijson!(1).coerce(Bool), None);
+        assert_eq!(ijson!(null).coerce(String), None);
+        assert_eq!(ijson!([1]).coerce(String), None);
+        assert_eq!(ijson!(1).coerce(Object), None);
+        assert_eq!(ijson!(1).coerce(Null), None);
+    }
+
+    #[mockalloc::test]
+    fn can_compare_unordered() {
+        let x = ijson!([1, 2, 3]);
+        let y = ijson!([3, 2, 1]);
+        assert!(x.eq_unordered(&y));
+        assert_ne!(x, y);
+
+        assert!(ijson!({"a": [[1, 2], {"b": [3, 4]}]})
+            .eq_unordered(&ijson!({"a": [{"b": [4, 3]}, [2, 1]]})));
+        assert!(!ijson!([1, 1, 2]).eq_unordered(&ijson!([1, 2, 2])));
+        assert!(!ijson!([1, 2]).eq_unordered(&ijson!([1, 2, 3])));
+        assert!(!ijson!({"a": [1]}).eq_unordered(&ijson!({"b": [1]})));
+        assert!(!ijson!(1).eq_unordered(&ijson!([1])));
+    }
+
+    #[mockalloc::test]
+    fn can_convert_to_exact_f64() {
+        assert_eq!(ijson!(0.25).f64_exact(), Ok(0.25));
+        assert_eq!(ijson!(-7).f64_exact(), Ok(-7.0));
+        assert_eq!(
+            IValue::from(9_007_199_254_740_993_i64).f64_exact(),
+            Err(NumberError::Inexact)
+        );
+        let err = ijson!("1.5").f64_exact().unwrap_err();
+        assert_eq!(
+            err,
+            NumberError::NotANumber(TypeError {
+                expected: ValueType::Number,
+                found: ValueType::String,
+            })
+        );
+        assert_eq!(err.to_string(), "expected number, found string");
+    }
+
+    #[mockalloc::test]
+    fn can_compare_approximately() {
+        let x = ijson!({"a": [1.0000001, "b"], "c": 1e20});
+        let y = ijson!({"a": [1.0, "b"], "c": 1.000_000_1e20});
+
+        assert!(x.approx_eq(&y, 1e-6));
+        assert!(!x.approx_eq(&y, 1e-8));
+        assert_ne!(x, y);
+        assert!(ijson!(0.0).approx_eq(&ijson!(1e-7), 1e-6));
+        assert!(!ijson!(["b"]).approx_eq(&ijson!(["c"]), 1.0));
+        assert!(!ijson!(1).approx_eq(&ijson!("1"), 1.0));
+        assert!(!ijson!({"a": 1}).approx_eq(&ijson!({"b": 1}), 1.0));
+    }
+
+    #[mockalloc::test]
+    fn can_check_subset() {
+        let response: IValue = ijson!({
+            "id": 1,
+            "name": "x",
+            "tags": ["a", "b", "c"],
+            "owner": { "id": 2, "roles": [{ "name": "admin", "level": 3 }] }
+        });
+
+        assert!(ijson!({}).is_subset_of(&response));
+        assert!(ijson!({ "id": 1, "name": "x" }).is_subset_of(&response));
+        assert!(response.is_subset_of(&response));
+        assert!(!ijson!({ "missing": 1 }).is_subset_of(&response));
+        assert!(!ijson!({ "id": 2 }).is_subset_of(&response));
+        assert!(!response.is_subset_of(&ijson!({ "id": 1 })));
+
+        // Nested objects and arrays
+        assert!(ijson!({ "tags": ["a", "b"] }).is_subset_of(&response));
+        assert!(!ijson!({ "tags": ["b"] }).is_subset_of(&response));
+        assert!(!ijson!({ "tags": ["a", "b", "c", "d"] }).is_subset_of(&response));
+        assert!(ijson!({ "owner": { "roles": [{ "name": "admin" }] } }).is_subset_of(&response));
+        assert!(!ijson!({ "owner": { "roles": [{ "name": "user" }] } }).is_subset_of(&response));
+        assert!(!ijson!({ "owner": { "email": null } }).is_subset_of(&response));
+
+        // Scalars and mismatched types
+        assert!(ijson!(1).is_subset_of(&ijson!(1)));
+        assert!(!ijson!(1).is_subset_of(&ijson!("1")));
+        assert!(!ijson!([]).is_subset_of(&ijson!({})));
+    }
 }